@@ -0,0 +1,108 @@
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use multiparty::server::sans_io::{FormData, Read};
+
+const BOUNDARY: &str = "--abcdef1234--";
+
+/// Build a multipart body made of `parts` parts, each with a body of `part_len` random bytes.
+fn body(parts: usize, part_len: usize) -> Bytes {
+    let mut body = String::new();
+    for i in 0..parts {
+        let contents = (0..part_len)
+            .map(|_| fastrand::alphanumeric())
+            .collect::<String>();
+        body.push_str(&format!(
+            "--{}\r\ncontent-disposition: form-data; name=\"field{}\"\r\n\r\n{}\r\n",
+            BOUNDARY, i, contents
+        ));
+    }
+    body.push_str(&format!("--{}--\r\n", BOUNDARY));
+    body.into()
+}
+
+/// Split `body` into `Bytes` of `chunk_size` bytes, like a network stream would.
+fn chunks(body: &Bytes, chunk_size: usize) -> Vec<Bytes> {
+    (0..body.len())
+        .step_by(chunk_size)
+        .map(|start| body.slice(start..body.len().min(start + chunk_size)))
+        .collect()
+}
+
+/// Decode `chunks`, returning the number of parts and of body bytes.
+fn decode(chunks: &[Bytes]) -> (usize, usize) {
+    let mut chunks = chunks.iter().cloned();
+    let mut form_data = FormData::new(BOUNDARY);
+
+    let mut parts = 0;
+    let mut len = 0;
+    loop {
+        match form_data.read().unwrap() {
+            Read::NeedsWrite => match chunks.next() {
+                Some(chunk) => form_data.write(chunk).unwrap(),
+                None => form_data.write_eof(),
+            },
+            Read::NewPart { headers, .. } => {
+                black_box(headers);
+                parts += 1;
+            }
+            Read::Part(bytes) => len += black_box(bytes).len(),
+            Read::PartEof | Read::None => {}
+            Read::Eof => return (parts, len),
+        }
+    }
+}
+
+fn bench(c: &mut Criterion, name: &str, body: Bytes, chunk_sizes: &[usize]) {
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(body.len() as u64));
+    for &chunk_size in chunk_sizes {
+        let chunks = chunks(&body, chunk_size);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(chunk_size),
+            &chunks,
+            |b, chunks| b.iter(|| decode(chunks)),
+        );
+    }
+    group.finish();
+}
+
+fn single_large_part(c: &mut Criterion) {
+    bench(
+        c,
+        "single_large_part",
+        body(1, 4 * 1024 * 1024),
+        &[8 * 1024, 64 * 1024],
+    );
+}
+
+fn many_small_parts(c: &mut Criterion) {
+    bench(
+        c,
+        "many_small_parts",
+        body(10_000, 16),
+        &[8 * 1024, 64 * 1024],
+    );
+}
+
+fn byte_at_a_time(c: &mut Criterion) {
+    bench(c, "byte_at_a_time", body(4, 1024), &[1]);
+}
+
+fn random_body(c: &mut Criterion) {
+    let body = body(1, 4096);
+    bench(
+        c,
+        "random_body",
+        body.clone(),
+        &[1, 7, 64, 1024, body.len()],
+    );
+}
+
+criterion_group!(
+    benches,
+    single_large_part,
+    many_small_parts,
+    byte_at_a_time,
+    random_body
+);
+criterion_main!(benches);
@@ -1,14 +1,147 @@
+//! Multipart boundary utilities
+
+use std::error::Error as StdError;
+use std::fmt::{self, Display};
+use std::str;
+
 use bytes::Bytes;
 
+use crate::headers::params;
+
+/// The maximum length of a boundary, as defined by RFC 2046
+const MAX_LEN: usize = 70;
+
+/// The length of the boundaries returned by [`generate`]
+#[cfg(feature = "random")]
+const GENERATED_LEN: usize = 32;
+
 /// A multipart boundary stored as `\r\n--{boundary}`
 #[derive(Debug, Clone)]
 pub struct Boundary(Bytes);
 
+/// Error returned by [`Boundary::try_new`] for boundaries not allowed by RFC 2046
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum BoundaryError {
+    /// The boundary is empty.
+    Empty,
+    /// The boundary is longer than 70 characters.
+    TooLong {
+        /// The length of the boundary
+        len: usize,
+    },
+    /// The boundary contains a character outside of the `bchars` set.
+    InvalidCharacter {
+        /// The byte offset of the character
+        index: usize,
+    },
+    /// The boundary ends with a space.
+    TrailingSpace,
+}
+
+/// Generate a random boundary.
+///
+/// The boundary is made of 32 ASCII alphanumeric characters,
+/// so it never needs to be quoted in the `Content-Type` header.
+///
+/// ```rust
+/// let boundary = multiparty::boundary::generate();
+/// assert_eq!(boundary.len(), 32);
+/// assert!(boundary.bytes().all(|b| b.is_ascii_alphanumeric()));
+/// ```
+#[cfg(feature = "random")]
+#[cfg_attr(docsrs, doc(cfg(feature = "random")))]
+pub fn generate() -> String {
+    (0..GENERATED_LEN)
+        .map(|_| fastrand::alphanumeric())
+        .collect::<String>()
+}
+
+/// Extract the boundary from the value of a `Content-Type` header.
+///
+/// Returns `None` if the media type isn't `multipart/*` or
+/// if the `boundary` parameter is missing or empty.
+///
+/// ```rust
+/// use multiparty::boundary::from_content_type;
+///
+/// let content_type = "multipart/form-data; boundary=----WebKitFormBoundaryXYZ";
+/// assert_eq!(from_content_type(content_type), Some("----WebKitFormBoundaryXYZ"));
+/// assert_eq!(from_content_type("multipart/mixed; boundary=\"a b\""), Some("a b"));
+/// assert_eq!(
+///     from_content_type("multipart/related; type=\"application/xop+xml\"; boundary=abcd"),
+///     Some("abcd")
+/// );
+/// assert_eq!(from_content_type("text/plain; boundary=abcd"), None);
+/// ```
+pub fn from_content_type(content_type: &str) -> Option<&str> {
+    let media_type = content_type.split(';').next().expect("always Some");
+    let mut splitter = media_type.splitn(2, '/');
+    let type_ = splitter.next().expect("always Some").trim();
+    let subtype = splitter.next()?.trim();
+    if !type_.eq_ignore_ascii_case("multipart") || subtype.is_empty() {
+        return None;
+    }
+
+    let boundary = params(content_type)
+        .find(|(name, _)| name.eq_ignore_ascii_case("boundary"))
+        .and_then(|(_, value)| value)?;
+    let boundary = match boundary.strip_prefix('"') {
+        Some(quoted) => quoted.strip_suffix('"')?,
+        None => boundary,
+    };
+
+    if boundary.is_empty() {
+        None
+    } else {
+        Some(boundary)
+    }
+}
+
 impl Boundary {
+    /// Construct a new `Boundary` without validating it.
     pub fn new(boundary: &str) -> Self {
         Self(format!("\r\n--{}", boundary).into())
     }
 
+    /// Construct a new `Boundary`, checking that it's allowed by RFC 2046.
+    ///
+    /// The boundary must be 1 to 70 characters long, only contain
+    /// characters from the `bchars` set and not end with a space.
+    ///
+    /// ```rust
+    /// use multiparty::boundary::{Boundary, BoundaryError};
+    ///
+    /// assert!(Boundary::try_new("----WebKitFormBoundaryXYZ").is_ok());
+    /// assert_eq!(
+    ///     Boundary::try_new("abcd\r\n").unwrap_err(),
+    ///     BoundaryError::InvalidCharacter { index: 4 }
+    /// );
+    /// ```
+    pub fn try_new(boundary: &str) -> Result<Self, BoundaryError> {
+        if boundary.is_empty() {
+            return Err(BoundaryError::Empty);
+        }
+        if boundary.len() > MAX_LEN {
+            return Err(BoundaryError::TooLong {
+                len: boundary.len(),
+            });
+        }
+        if let Some(index) = boundary.bytes().position(|b| !is_bchar(b)) {
+            return Err(BoundaryError::InvalidCharacter { index });
+        }
+        if boundary.ends_with(' ') {
+            return Err(BoundaryError::TrailingSpace);
+        }
+
+        Ok(Self::new(boundary))
+    }
+
+    /// Get the boundary, as it was passed to the constructor
+    pub fn as_str(&self) -> &str {
+        str::from_utf8(&self.0["\r\n--".len()..]).expect("constructed from a str")
+    }
+
     /// Equivalent to `format!("--{}", boundary)`
     pub fn with_dashes(&self) -> Bytes {
         self.0.slice("\r\n".len()..)
@@ -18,6 +151,61 @@ impl Boundary {
     pub fn with_new_line_and_dashes(&self) -> Bytes {
         self.0.clone()
     }
+
+    /// Equivalent to `format!("\n--{}", boundary)`
+    pub fn with_line_feed_and_dashes(&self) -> Bytes {
+        self.0.slice("\r".len()..)
+    }
+
+    /// Like [`Boundary::with_new_line_and_dashes`], but borrowed instead of cloned
+    pub fn as_new_line_and_dashes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Like [`Boundary::with_line_feed_and_dashes`], but borrowed instead of cloned
+    pub fn as_line_feed_and_dashes(&self) -> &[u8] {
+        &self.0["\r".len()..]
+    }
+}
+
+impl Display for BoundaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("boundary is empty"),
+            Self::TooLong { len } => write!(
+                f,
+                "boundary is {} characters long, more than the limit of {}",
+                len, MAX_LEN
+            ),
+            Self::InvalidCharacter { index } => {
+                write!(f, "boundary contains an invalid character at {}", index)
+            }
+            Self::TrailingSpace => f.write_str("boundary ends with a space"),
+        }
+    }
+}
+
+impl StdError for BoundaryError {}
+
+/// Determine if `b` is part of the RFC 2046 `bchars` set
+fn is_bchar(b: u8) -> bool {
+    b.is_ascii_alphanumeric()
+        || matches!(
+            b,
+            b'\''
+                | b'('
+                | b')'
+                | b'+'
+                | b'_'
+                | b','
+                | b'-'
+                | b'.'
+                | b'/'
+                | b':'
+                | b'='
+                | b'?'
+                | b' '
+        )
 }
 
 #[cfg(test)]
@@ -29,5 +217,80 @@ mod tests {
         let boundary = Boundary::new("abcd");
         assert_eq!(boundary.with_dashes(), "--abcd");
         assert_eq!(boundary.with_new_line_and_dashes(), "\r\n--abcd");
+        assert_eq!(boundary.with_line_feed_and_dashes(), "\n--abcd");
+        assert_eq!(boundary.as_new_line_and_dashes(), b"\r\n--abcd");
+        assert_eq!(boundary.as_line_feed_and_dashes(), b"\n--abcd");
+        assert_eq!(boundary.as_str(), "abcd");
+    }
+
+    #[test]
+    fn validate() {
+        assert!(Boundary::try_new("abcd").is_ok());
+        assert!(Boundary::try_new("----WebKitFormBoundary7MA4YWxkTrZu0gW").is_ok());
+        assert!(Boundary::try_new("'()+_,-./:=? a").is_ok());
+        assert!(Boundary::try_new(&"a".repeat(70)).is_ok());
+
+        assert_eq!(Boundary::try_new("").unwrap_err(), BoundaryError::Empty);
+        assert_eq!(
+            Boundary::try_new(&"a".repeat(71)).unwrap_err(),
+            BoundaryError::TooLong { len: 71 }
+        );
+        assert_eq!(
+            Boundary::try_new("ab\"cd").unwrap_err(),
+            BoundaryError::InvalidCharacter { index: 2 }
+        );
+        assert_eq!(
+            Boundary::try_new("abcdé").unwrap_err(),
+            BoundaryError::InvalidCharacter { index: 4 }
+        );
+        assert_eq!(
+            Boundary::try_new("abcd ").unwrap_err(),
+            BoundaryError::TrailingSpace
+        );
+    }
+
+    #[test]
+    fn content_type() {
+        assert_eq!(
+            from_content_type("multipart/form-data; boundary=abcd"),
+            Some("abcd")
+        );
+        assert_eq!(
+            from_content_type("Multipart/Form-Data;charset=utf-8;BOUNDARY=abcd"),
+            Some("abcd")
+        );
+        assert_eq!(
+            from_content_type("multipart/form-data; name=\"x;y\"; boundary=\"ab cd\""),
+            Some("ab cd")
+        );
+        assert_eq!(from_content_type("multipart/form-data"), None);
+        assert_eq!(from_content_type("multipart/form-data; boundary="), None);
+        assert_eq!(
+            from_content_type("multipart/form-data; boundary=\"\""),
+            None
+        );
+        assert_eq!(
+            from_content_type("multipart/form-data; boundary=\"abcd"),
+            None
+        );
+        assert_eq!(
+            from_content_type(
+                "multipart/related; type=\"application/xop+xml\"; \
+                 start=\"<root;1>\"; start-info=\"text/xml\"; boundary=\"MIME_boundary\""
+            ),
+            Some("MIME_boundary")
+        );
+        assert_eq!(from_content_type("multipart; boundary=abcd"), None);
+        assert_eq!(from_content_type("application/json; boundary=abcd"), None);
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn generated() {
+        let boundary1 = generate();
+        let boundary2 = generate();
+        assert_eq!(boundary1.len(), GENERATED_LEN);
+        assert!(boundary1.bytes().all(|b| b.is_ascii_alphanumeric()));
+        assert_ne!(boundary1, boundary2);
     }
 }
@@ -0,0 +1,322 @@
+//! `multipart/form-data` encoder.
+//!
+//! ```rust
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! use multiparty::client::encoder::MultipartEncoder;
+//! use futures_util::stream::StreamExt;
+//!
+//! let mut encoder = MultipartEncoder::new();
+//! encoder.add_bytes("foo", None, None, "bar");
+//! encoder.add_bytes("abcd", Some("test.txt"), Some("text/plain"), "efgh");
+//!
+//! let content_type = encoder.content_type();
+//! println!("Content-Type: {}", content_type);
+//!
+//! let mut body = encoder.into_stream();
+//! while let Some(bytes) = body.next().await {
+//!     println!("Write {} bytes to the request body", bytes.len());
+//! }
+//! # }
+//! ```
+
+use std::fmt::{self, Debug};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::vec;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_core::Stream;
+
+use crate::boundary::{self, Boundary};
+
+/// A `multipart/form-data` encoder.
+///
+/// Fields are added through [`MultipartEncoder::add_bytes`] and
+/// [`MultipartEncoder::add_stream`], then the body is obtained
+/// through [`MultipartEncoder::into_stream`].
+pub struct MultipartEncoder {
+    boundary: String,
+    fields: Vec<Field>,
+}
+
+/// The `Stream` of `Bytes` making up an encoded `multipart/form-data` body.
+///
+/// Obtained through [`MultipartEncoder::into_stream`].
+pub struct Encoded {
+    boundary: Boundary,
+    fields: vec::IntoIter<Field>,
+    body: Option<Body>,
+    first: bool,
+    done: bool,
+}
+
+struct Field {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    body: Body,
+}
+
+enum Body {
+    Bytes(Bytes),
+    Stream(Pin<Box<dyn Stream<Item = Bytes> + Send>>),
+}
+
+impl MultipartEncoder {
+    /// Construct a new `MultipartEncoder` with a random boundary.
+    ///
+    /// See [`boundary::generate`].
+    pub fn new() -> Self {
+        Self::with_boundary(&boundary::generate())
+    }
+
+    /// Construct a new `MultipartEncoder` using `boundary` to delimit parts.
+    ///
+    /// The boundary must not appear in any of the bodies.
+    pub fn with_boundary(boundary: &str) -> Self {
+        Self {
+            boundary: boundary.to_owned(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Get the boundary delimiting the parts of the body.
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Get the value of the `Content-Type` header of the body.
+    ///
+    /// Equivalent to `format!("multipart/form-data; boundary={}", boundary)`,
+    /// except that boundaries which aren't a valid token, like ones
+    /// containing a space, are quoted.
+    pub fn content_type(&self) -> String {
+        if !self.boundary.is_empty() && self.boundary.bytes().all(is_token) {
+            return format!("multipart/form-data; boundary={}", self.boundary);
+        }
+
+        let mut content_type = String::from("multipart/form-data; boundary=\"");
+        for c in self.boundary.chars() {
+            if c == '"' || c == '\\' {
+                content_type.push('\\');
+            }
+            content_type.push(c);
+        }
+        content_type.push('"');
+        content_type
+    }
+
+    /// Add a field whose body is already in memory.
+    pub fn add_bytes<B>(
+        &mut self,
+        name: &str,
+        filename: Option<&str>,
+        content_type: Option<&str>,
+        body: B,
+    ) where
+        B: Into<Bytes>,
+    {
+        self.add(name, filename, content_type, Body::Bytes(body.into()));
+    }
+
+    /// Add a field whose body is read from a `Stream`.
+    pub fn add_stream<S>(
+        &mut self,
+        name: &str,
+        filename: Option<&str>,
+        content_type: Option<&str>,
+        body: S,
+    ) where
+        S: Stream<Item = Bytes> + Send + 'static,
+    {
+        self.add(name, filename, content_type, Body::Stream(Box::pin(body)));
+    }
+
+    fn add(&mut self, name: &str, filename: Option<&str>, content_type: Option<&str>, body: Body) {
+        self.fields.push(Field {
+            name: name.to_owned(),
+            filename: filename.map(str::to_owned),
+            content_type: content_type.map(str::to_owned),
+            body,
+        });
+    }
+
+    /// Encode the fields into a `Stream` of `Bytes`.
+    pub fn into_stream(self) -> Encoded {
+        Encoded {
+            boundary: Boundary::new(&self.boundary),
+            fields: self.fields.into_iter(),
+            body: None,
+            first: true,
+            done: false,
+        }
+    }
+}
+
+impl Default for MultipartEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for MultipartEncoder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MultipartEncoder")
+            .field("boundary", &self.boundary)
+            .finish()
+    }
+}
+
+impl Encoded {
+    /// The delimiter preceding the next part or the closing delimiter
+    fn delimiter(&mut self) -> Bytes {
+        if self.first {
+            self.first = false;
+            self.boundary.with_dashes()
+        } else {
+            self.boundary.with_new_line_and_dashes()
+        }
+    }
+}
+
+impl Stream for Encoded {
+    type Item = Bytes;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        match &mut this.body {
+            Some(Body::Bytes(bytes)) => {
+                let bytes = std::mem::take(bytes);
+                this.body = None;
+
+                if !bytes.is_empty() {
+                    return Poll::Ready(Some(bytes));
+                }
+            }
+            Some(Body::Stream(stream)) => match stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(bytes)) => return Poll::Ready(Some(bytes)),
+                Poll::Ready(None) => this.body = None,
+                Poll::Pending => return Poll::Pending,
+            },
+            None => {}
+        }
+
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        let delimiter = this.delimiter();
+        match this.fields.next() {
+            Some(field) => {
+                let mut head = BytesMut::new();
+                head.put(delimiter);
+                field.write_headers(&mut head);
+
+                this.body = Some(field.body);
+                Poll::Ready(Some(head.freeze()))
+            }
+            None => {
+                this.done = true;
+
+                let mut tail = BytesMut::with_capacity(delimiter.len() + "--\r\n".len());
+                tail.put(delimiter);
+                tail.put_slice(b"--\r\n");
+                Poll::Ready(Some(tail.freeze()))
+            }
+        }
+    }
+}
+
+impl Debug for Encoded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Encoded").finish()
+    }
+}
+
+impl Field {
+    /// Write the line ending of the delimiter and the headers of this field
+    fn write_headers(&self, buf: &mut BytesMut) {
+        buf.put_slice(b"\r\ncontent-disposition: form-data; name=\"");
+        write_quoted(buf, &self.name);
+        buf.put_slice(b"\"");
+
+        if let Some(filename) = &self.filename {
+            buf.put_slice(b"; filename=\"");
+            write_quoted(buf, filename);
+            buf.put_slice(b"\"");
+        }
+
+        if let Some(content_type) = &self.content_type {
+            buf.put_slice(b"\r\ncontent-type: ");
+            write_header_value(buf, content_type);
+        }
+
+        buf.put_slice(b"\r\n\r\n");
+    }
+}
+
+/// Write the contents of a quoted-string, percent encoding `"`, `\r` and `\n`
+/// like browsers do
+fn write_quoted(buf: &mut BytesMut, value: &str) {
+    for c in value.chars() {
+        match c {
+            '"' => buf.put_slice(b"%22"),
+            '\r' => buf.put_slice(b"%0D"),
+            '\n' => buf.put_slice(b"%0A"),
+            c => {
+                let mut tmp = [0; 4];
+                buf.put_slice(c.encode_utf8(&mut tmp).as_bytes());
+            }
+        }
+    }
+}
+
+/// Write a header value, percent encoding `\r` and `\n` so that
+/// it can't end the header early
+fn write_header_value(buf: &mut BytesMut, value: &str) {
+    for &b in value.as_bytes() {
+        match b {
+            b'\r' => buf.put_slice(b"%0D"),
+            b'\n' => buf.put_slice(b"%0A"),
+            b => buf.put_u8(b),
+        }
+    }
+}
+
+/// Whether `b` can be part of a token, as defined by RFC 7230
+fn is_token(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted() {
+        let mut buf = BytesMut::new();
+        write_quoted(&mut buf, "a\"b\r\nc\\d.txt");
+        assert_eq!(buf, "a%22b%0D%0Ac\\d.txt");
+    }
+
+    #[test]
+    fn header_value() {
+        let mut buf = BytesMut::new();
+        write_header_value(&mut buf, "text/plain\r\nx-injected: 1");
+        assert_eq!(buf, "text/plain%0D%0Ax-injected: 1");
+    }
+
+    #[test]
+    fn quoted_boundary() {
+        let encoder = MultipartEncoder::with_boundary("abcd");
+        assert_eq!(encoder.content_type(), "multipart/form-data; boundary=abcd");
+
+        let encoder = MultipartEncoder::with_boundary("ab cd:ef");
+        assert_eq!(
+            encoder.content_type(),
+            "multipart/form-data; boundary=\"ab cd:ef\""
+        );
+    }
+}
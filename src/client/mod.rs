@@ -0,0 +1,3 @@
+//! Multipart encoder implementations
+
+pub mod encoder;
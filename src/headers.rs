@@ -1,83 +1,271 @@
 //! Headers
 
+use std::borrow::Cow;
 use std::error::Error as StdError;
 use std::fmt::{self, Debug, Display};
-use std::str;
+use std::iter;
+use std::str::{self, Utf8Error};
 
 use bytes::Bytes;
+#[cfg(feature = "encoding")]
+use encoding_rs::Encoding;
 
 /// Raw unparsed headers
 #[derive(Debug, Clone)]
 pub struct RawHeaders {
     headers: Vec<(Bytes, Bytes)>,
+    raw: Bytes,
 }
 
 impl RawHeaders {
+    #[cfg(test)]
     pub(crate) fn new(headers: Vec<(Bytes, Bytes)>) -> Self {
-        Self { headers }
+        Self::with_raw(headers, Bytes::new())
     }
 
-    /// Parse the `Content-Disposition` and the `Content-Type` headers.
+    #[cfg(any(test, feature = "server"))]
+    pub(crate) fn with_raw(headers: Vec<(Bytes, Bytes)>, raw: Bytes) -> Self {
+        Self { headers, raw }
+    }
+
+    #[cfg(feature = "server")]
+    pub(crate) fn into_headers(self) -> Vec<(Bytes, Bytes)> {
+        self.headers
+    }
+
+    /// Parse a block of headers, like the ones at the start of each part.
+    ///
+    /// `block` must contain the headers followed by the empty line ending them.
+    /// Anything after the empty line is ignored. The names and the values of
+    /// the headers borrow from `block`. At most
+    /// [`DEFAULT_MAX_HEADERS`](crate::server::sans_io::DEFAULT_MAX_HEADERS)
+    /// headers are accepted.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use bytes::Bytes;
+    /// use multiparty::headers::RawHeaders;
+    ///
+    /// let block = Bytes::from_static(b"Content-Disposition: form-data; name=\"foo\"\r\n\r\n");
+    /// let headers = RawHeaders::from_bytes(&block)?;
+    /// assert_eq!(headers.parse()?.name, "foo");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "server")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+    pub fn from_bytes(block: &Bytes) -> Result<Self, Error> {
+        let mut headers = vec![httparse::EMPTY_HEADER; crate::server::sans_io::DEFAULT_MAX_HEADERS];
+
+        match httparse::parse_headers(block, &mut headers) {
+            Ok(httparse::Status::Complete((read, headers))) => {
+                let headers = headers
+                    .iter()
+                    .map(|header| {
+                        let name = block.slice_ref(header.name.as_bytes());
+                        let value = block.slice_ref(header.value);
+                        (name, value)
+                    })
+                    .collect();
+                Ok(Self::with_raw(headers, block.slice(..read)))
+            }
+            Ok(httparse::Status::Partial) => Err(Error(InnerError::IncompleteHeaders)),
+            Err(httparse::Error::HeaderName) => {
+                // Whitespace between the header names and the colon is tolerated
+                let trimmed = crate::server::sans_io::headers_len(block)
+                    .and_then(|len| crate::server::sans_io::trim_header_names(&block[..len]));
+                match trimmed {
+                    Some(trimmed) => Self::from_bytes(&trimmed),
+                    None => Err(Error(InnerError::Headers(httparse::Error::HeaderName))),
+                }
+            }
+            Err(err) => Err(Error(InnerError::Headers(err))),
+        }
+    }
+
+    /// The raw bytes of the headers, including the empty line after them.
+    ///
+    /// Useful when the decoder has been told not to collect the headers.
+    /// Empty if these `RawHeaders` weren't produced by the decoder.
+    pub fn as_bytes(&self) -> &Bytes {
+        &self.raw
+    }
+
+    /// Parse the `Content-Disposition`, `Content-Type`, `Content-Length` and `Content-ID` headers.
+    ///
+    /// If a header is repeated, only the first one is parsed.
+    /// Use [`RawHeaders::get_all`] to look at the others.
     pub fn parse(&self) -> Result<Headers, Error> {
-        let (name, filename) = self.parse_content_disposition()?;
-        let name = name.to_string();
-        let filename = filename.map(|filename| filename.to_string());
+        self.parse_zero_copy().map(HeadersRef::into_owned)
+    }
+
+    /// Parse the `Content-Disposition`, `Content-Type`, `Content-Length` and `Content-ID` headers,
+    /// configuring how they're parsed through `options`.
+    pub fn parse_with(&self, options: ParseOptions) -> Result<Headers, Error> {
+        self.parse_zero_copy_with(options)
+            .map(HeadersRef::into_owned)
+    }
 
+    /// Parse the `Content-Disposition`, `Content-Type`, `Content-Length` and `Content-ID` headers,
+    /// borrowing from these `RawHeaders` instead of allocating.
+    ///
+    /// Values which need to be unescaped or decoded are still allocated.
+    pub fn parse_zero_copy(&self) -> Result<HeadersRef<'_>, Error> {
+        self.parse_zero_copy_with(ParseOptions::default())
+    }
+
+    /// Parse the `Content-Disposition`, `Content-Type`, `Content-Length` and `Content-ID` headers,
+    /// borrowing from these `RawHeaders` and configuring how they're parsed
+    /// through `options`.
+    pub fn parse_zero_copy_with(&self, options: ParseOptions) -> Result<HeadersRef<'_>, Error> {
+        let content_disposition = self.parse_content_disposition();
+        #[cfg(feature = "encoding")]
+        let content_disposition = match (content_disposition, options.encoding) {
+            (Err(Error(InnerError::ContentDispositionUtf8)), Some(encoding)) => {
+                self.parse_encoded_content_disposition(encoding)
+            }
+            (content_disposition, _) => content_disposition,
+        };
+        let ContentDisposition {
+            disposition,
+            name,
+            mut filename,
+        } = content_disposition?;
+        if options.decode_filename_percent {
+            filename = filename.map(percent_decode_filename);
+        }
         let content_type = self.parse_content_type()?;
-        let content_type = content_type.map(|content_type| content_type.to_string());
+        // Malformed optional headers don't make the whole part unusable
+        let content_length = self.content_length().ok().flatten();
+        let content_id = self.content_id().ok().flatten();
+        let extra = if options.collect_extra {
+            self.extra().collect()
+        } else {
+            Vec::new()
+        };
 
-        Ok(Headers {
+        Ok(HeadersRef {
+            disposition,
             name,
             filename,
             content_type,
+            content_length,
+            content_id,
+            extra,
         })
     }
 
-    fn parse_content_disposition(&self) -> Result<(&str, Option<&str>), Error> {
-        let content_disposition = self
-            .header("content-disposition")
-            .ok_or(Error(InnerError::ContentDispositionNotFound))?;
+    /// Parse the `name` parameter of the `Content-Disposition` header.
+    ///
+    /// Unlike [`RawHeaders::parse`] this doesn't look at the `Content-Type` header.
+    /// The name is only allocated if it needs to be unescaped or decoded.
+    pub fn name(&self) -> Result<Cow<'_, str>, Error> {
+        self.parse_content_disposition()
+            .map(|content_disposition| content_disposition.name)
+    }
 
-        let content_disposition = str::from_utf8(content_disposition)
-            .map_err(|_| Error(InnerError::ContentDispositionUtf8))?;
+    /// Parse the `filename` parameter of the `Content-Disposition` header.
+    ///
+    /// Unlike [`RawHeaders::parse`] this doesn't look at the `Content-Type` header.
+    /// The filename is only allocated if it needs to be unescaped or decoded.
+    pub fn filename(&self) -> Result<Option<Cow<'_, str>>, Error> {
+        self.parse_content_disposition()
+            .map(|content_disposition| content_disposition.filename)
+    }
 
-        let content_disposition = content_disposition
-            .strip_prefix("form-data")
-            .ok_or(Error(InnerError::ContentDispositionNotFormData))?;
+    /// Parse every parameter of the `Content-Disposition` header.
+    ///
+    /// Quoted values are unquoted. Parameters without a value are skipped.
+    pub fn content_disposition_params(&self) -> Result<Vec<(String, String)>, Error> {
+        let (_, content_disposition) = self.content_disposition()?;
 
-        // Parse the `name` and `filename` from the content-disposition
-        let mut name = None;
-        let mut filename = None;
+        let params = params(content_disposition)
+            .filter_map(|(name, value)| {
+                value.map(|value| (name.to_string(), unquote(value).into_owned()))
+            })
+            .collect();
+        Ok(params)
+    }
 
-        for param in content_disposition.split(';').skip(1) {
-            let param = param.trim();
+    /// The raw value of the `Content-Disposition` header.
+    pub fn content_disposition_raw(&self) -> Option<&[u8]> {
+        self.get("content-disposition").map(|value| &value[..])
+    }
 
-            let mut splitter = param.split('=');
-            let param_name = splitter.next().expect("always Some");
+    /// The raw value of the `Content-Disposition` header, validated to be UTF-8.
+    ///
+    /// Returns `Ok(None)` if the part doesn't have a `Content-Disposition` header.
+    pub fn content_disposition_str(&self) -> Result<Option<&str>, Error> {
+        match self.content_disposition_raw() {
+            Some(value) => str::from_utf8(value)
+                .map(Some)
+                .map_err(|_| Error(InnerError::ContentDispositionUtf8)),
+            None => Ok(None),
+        }
+    }
 
-            if param_name != "name" && param_name != "filename" {
-                continue;
-            }
+    /// The headers other than the ones parsed into the fields of [`Headers`]
+    fn extra(&self) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, str>)> {
+        self.headers
+            .iter()
+            .filter(|(name, _value)| {
+                !MODELED_HEADERS
+                    .iter()
+                    .any(|modeled| trim_whitespace(name).eq_ignore_ascii_case(modeled.as_bytes()))
+            })
+            .map(|(name, value)| {
+                (
+                    String::from_utf8_lossy(name),
+                    String::from_utf8_lossy(value),
+                )
+            })
+    }
 
-            let param_value = splitter
-                .next()
-                .ok_or(Error(InnerError::InvalidContentDispositionParam))?;
-            let param_value = param_value.trim_matches(|c: char| c.is_whitespace() || c == '"');
+    /// Get the type and the value of the `Content-Disposition` header
+    fn content_disposition(&self) -> Result<(DispositionType, &str), Error> {
+        let content_disposition = self
+            .content_disposition_str()?
+            .ok_or(Error(InnerError::ContentDispositionNotFound))?;
 
-            if param_name == "name" {
-                name = Some(param_value);
-            } else {
-                filename = Some(param_value);
-            }
-        }
+        let disposition = disposition_type(content_disposition)?;
+        Ok((disposition, content_disposition))
+    }
 
-        let name = name.ok_or(Error(InnerError::NoContentDispositionName))?;
+    fn parse_content_disposition(&self) -> Result<ContentDisposition<'_>, Error> {
+        let (disposition, content_disposition) = self.content_disposition()?;
+        parse_content_disposition(disposition, content_disposition)
+    }
+
+    /// Parse the `Content-Disposition` header after decoding it from `encoding`.
+    #[cfg(feature = "encoding")]
+    fn parse_encoded_content_disposition(
+        &self,
+        encoding: &'static Encoding,
+    ) -> Result<ContentDisposition<'static>, Error> {
+        let content_disposition = self
+            .content_disposition_raw()
+            .ok_or(Error(InnerError::ContentDispositionNotFound))?;
+        let content_disposition = encoding
+            .decode_without_bom_handling_and_without_replacement(content_disposition)
+            .ok_or(Error(InnerError::ContentDispositionEncoding(
+                encoding.name(),
+            )))?;
 
-        Ok((name, filename))
+        let disposition = disposition_type(&content_disposition)?;
+        let ContentDisposition {
+            disposition,
+            name,
+            filename,
+        } = parse_content_disposition(disposition, &content_disposition)?;
+        Ok(ContentDisposition {
+            disposition,
+            name: Cow::Owned(name.into_owned()),
+            filename: filename.map(|filename| Cow::Owned(filename.into_owned())),
+        })
     }
 
     fn parse_content_type(&self) -> Result<Option<&str>, Error> {
-        match self.header("content-type") {
+        match self.get("content-type") {
             Some(value) => {
                 let value =
                     str::from_utf8(value).map_err(|_| Error(InnerError::ContentTypeUtf8))?;
@@ -87,39 +275,567 @@ impl RawHeaders {
         }
     }
 
-    fn header(&self, name: &str) -> Option<&Bytes> {
+    /// Parse the `Content-Type` header into its media type and parameters.
+    ///
+    /// Returns `Ok(None)` if the part doesn't have a `Content-Type` header.
+    pub fn content_type(&self) -> Result<Option<ContentType>, Error> {
+        self.parse_content_type()
+            .map(|content_type| content_type.map(ContentType::parse))
+    }
+
+    /// Parse the `Content-Length` header, which some clients send to
+    /// declare the length of the body of the part.
+    ///
+    /// Returns `Ok(None)` if the part doesn't have a `Content-Length` header.
+    pub fn content_length(&self) -> Result<Option<u64>, Error> {
+        let value = match self.get("content-length") {
+            Some(value) => trim_whitespace(value),
+            None => return Ok(None),
+        };
+
+        if value.is_empty() || !value.iter().all(u8::is_ascii_digit) {
+            return Err(Error(InnerError::InvalidContentLength));
+        }
+        str::from_utf8(value)
+            .expect("ASCII digits")
+            .parse()
+            .map(Some)
+            .map_err(|_| Error(InnerError::InvalidContentLength))
+    }
+
+    /// Get the value of the `Content-ID` header, which identifies the parts
+    /// of `multipart/related` bodies.
+    ///
+    /// The value is trimmed, but the angle brackets around it are kept,
+    /// like in `<part1@example.com>`. Returns `Ok(None)` if the part
+    /// doesn't have a `Content-ID` header.
+    pub fn content_id(&self) -> Result<Option<&str>, Error> {
+        match self.get("content-id") {
+            Some(value) => str::from_utf8(trim_whitespace(value))
+                .map(Some)
+                .map_err(|_| Error(InnerError::ContentIdUtf8)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the boundary of the nested multipart body of this part.
+    ///
+    /// Returns `None` unless the `Content-Type` header is a `multipart/*`
+    /// media type with a `boundary` parameter, like `multipart/mixed; boundary=abcd`.
+    pub fn nested_boundary(&self) -> Option<&str> {
+        let content_type = self.parse_content_type().ok()??;
+        crate::boundary::from_content_type(content_type)
+    }
+
+    /// Iterate over every header, in the order they were received.
+    pub fn iter(&self) -> impl Iterator<Item = (&Bytes, &Bytes)> {
+        self.headers.iter().map(|(name, value)| (name, value))
+    }
+
+    /// Get the value of the first header named `name`, ignoring ASCII case
+    /// and whitespace around the header names.
+    pub fn get(&self, name: &str) -> Option<&Bytes> {
         let name = name.as_bytes();
         self.headers
             .iter()
-            .find(|(name_, _value)| name_.eq_ignore_ascii_case(name))
+            .find(|(name_, _value)| trim_whitespace(name_).eq_ignore_ascii_case(name))
             .map(|(_name, value)| value)
     }
+
+    /// Get the value of the first header named `name` as a `&str`.
+    ///
+    /// Like [`RawHeaders::get`] this ignores ASCII case and whitespace around
+    /// the header names. The value is borrowed instead of being copied,
+    /// and only the UTF-8 of the found value gets validated.
+    pub fn get_str(&self, name: &str) -> Option<Result<&str, Utf8Error>> {
+        self.get(name).map(|value| str::from_utf8(value))
+    }
+
+    /// Iterate over the values of every header named `name`, in the order they were received.
+    ///
+    /// Like [`RawHeaders::get`] this ignores ASCII case and whitespace around the header names.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Bytes> + 'a {
+        let name = name.as_bytes();
+        self.headers
+            .iter()
+            .filter(move |(name_, _value)| trim_whitespace(name_).eq_ignore_ascii_case(name))
+            .map(|(_name, value)| value)
+    }
+}
+
+/// Remove the ASCII whitespace around `bytes`
+fn trim_whitespace(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |end| end + 1);
+    &bytes[start..end]
+}
+
+/// The parsed parameters of a `Content-Disposition` header
+struct ContentDisposition<'a> {
+    disposition: DispositionType,
+    name: Cow<'a, str>,
+    filename: Option<Cow<'a, str>>,
+}
+
+/// Iterate over the `name=value` parameters in `header`, skipping what comes before the first `;`
+pub(crate) fn params(header: &str) -> impl Iterator<Item = (&str, Option<&str>)> {
+    split_unquoted(header, ';').skip(1).map(|param| {
+        let mut splitter = param.splitn(2, '=');
+        let name = splitter.next().expect("always Some").trim();
+        let value = splitter.next().map(str::trim);
+        (name, value)
+    })
+}
+
+/// Split `s` on `separator`, ignoring separators inside quoted strings
+fn split_unquoted(mut s: &str, separator: char) -> impl Iterator<Item = &str> {
+    let mut done = false;
+
+    iter::from_fn(move || {
+        if done {
+            return None;
+        }
+
+        let mut quoted = false;
+        let mut escaped = false;
+        for (i, c) in s.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if quoted && c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                quoted = !quoted;
+            } else if !quoted && c == separator {
+                let item = &s[..i];
+                s = &s[i + c.len_utf8()..];
+                return Some(item);
+            }
+        }
+
+        done = true;
+        Some(s)
+    })
+}
+
+/// Determine if `value` opens a quoted string which is never closed
+fn is_unterminated(value: &str) -> bool {
+    match value.strip_prefix('"') {
+        Some(quoted) => !quoted.contains('"'),
+        None => false,
+    }
+}
+
+/// Get the type of the `Content-Disposition` header with the value `content_disposition`
+fn disposition_type(content_disposition: &str) -> Result<DispositionType, Error> {
+    let disposition = content_disposition
+        .split(';')
+        .next()
+        .expect("always Some")
+        .trim();
+    let disposition = if disposition.eq_ignore_ascii_case("form-data") {
+        DispositionType::FormData
+    } else if disposition.eq_ignore_ascii_case("attachment") {
+        DispositionType::Attachment
+    } else if disposition.eq_ignore_ascii_case("inline") {
+        DispositionType::Inline
+    } else {
+        return Err(Error(InnerError::UnknownContentDispositionType));
+    };
+    Ok(disposition)
+}
+
+/// Parse the `name` and `filename` parameters of the `Content-Disposition` header
+fn parse_content_disposition(
+    disposition: DispositionType,
+    content_disposition: &str,
+) -> Result<ContentDisposition<'_>, Error> {
+    // Parse the `name` and `filename` from the content-disposition
+    let mut name = None;
+    let mut name_ext = None;
+    let mut filename = None;
+    let mut filename_ext = None;
+
+    for (param_name, param_value) in params(content_disposition) {
+        if !matches!(param_name, "name" | "name*" | "filename" | "filename*") {
+            continue;
+        }
+
+        let param_value = param_value.ok_or(Error(InnerError::InvalidContentDispositionParam))?;
+        if is_unterminated(param_value) {
+            // Most likely a value containing a line break, which
+            // made the rest of it end up on the next line
+            return Err(Error(InnerError::UnterminatedQuotedString));
+        }
+        let param_value = unquote(param_value);
+
+        match param_name {
+            "name" => name = Some(param_value),
+            "name*" => {
+                let decoded =
+                    decode_ext_value(&param_value).ok_or(Error(InnerError::InvalidExtendedName))?;
+                name_ext = Some(decoded);
+            }
+            "filename" => filename = Some(param_value),
+            _ => {
+                let decoded = decode_ext_value(&param_value)
+                    .ok_or(Error(InnerError::InvalidExtendedFilename))?;
+                filename_ext = Some(decoded);
+            }
+        }
+    }
+
+    // `name*` takes precedence over `name`
+    let name = match name_ext.map(Cow::Owned).or(name) {
+        Some(name) => name,
+        // Only `form-data` requires a `name`
+        None if disposition != DispositionType::FormData => Cow::Borrowed(""),
+        None => return Err(Error(InnerError::NoContentDispositionName)),
+    };
+    // `filename*` takes precedence over `filename`
+    let filename = filename_ext.map(Cow::Owned).or(filename);
+
+    Ok(ContentDisposition {
+        disposition,
+        name,
+        filename,
+    })
+}
+
+/// Remove the quotes around a parameter value, unescaping `\"` and `\\` inside quoted strings
+fn unquote(value: &str) -> Cow<'_, str> {
+    let quoted = match value.strip_prefix('"') {
+        Some(quoted) => quoted,
+        None => return Cow::Borrowed(value.trim_matches(|c: char| c.is_whitespace() || c == '"')),
+    };
+
+    if !quoted.contains('\\') {
+        let unquoted = match quoted.find('"') {
+            Some(end) => &quoted[..end],
+            None => quoted,
+        };
+        return Cow::Borrowed(unquoted);
+    }
+
+    let mut unquoted = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.peek() {
+                Some(&escaped) if escaped == '"' || escaped == '\\' => {
+                    unquoted.push(escaped);
+                    chars.next();
+                }
+                // Not an escape sequence, like in unescaped windows paths
+                _ => unquoted.push(c),
+            },
+            '"' => break,
+            c => unquoted.push(c),
+        }
+    }
+    Cow::Owned(unquoted)
+}
+
+/// Decode an RFC 5987 `ext-value`, like `UTF-8''%E2%82%AC.txt`
+fn decode_ext_value(value: &str) -> Option<String> {
+    let mut splitter = value.splitn(3, '\'');
+    let charset = splitter.next().expect("always Some");
+    let _language = splitter.next()?;
+    let value = splitter.next()?;
+
+    let value = percent_decode(value)?;
+    if charset.eq_ignore_ascii_case("utf-8") {
+        String::from_utf8(value).ok()
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+        Some(value.into_iter().map(char::from).collect())
+    } else {
+        None
+    }
+}
+
+/// Decode `%XX` sequences in a `filename`, leaving it untouched if that fails
+fn percent_decode_filename(filename: Cow<'_, str>) -> Cow<'_, str> {
+    if !filename.contains('%') {
+        return filename;
+    }
+
+    match percent_decode(&filename).and_then(|decoded| String::from_utf8(decoded).ok()) {
+        Some(decoded) => Cow::Owned(decoded),
+        None => filename,
+    }
+}
+
+/// Decode `%XX` sequences in `value`, returning `None` if any of them is invalid
+fn percent_decode(value: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(value.len());
+
+    let mut bytes = value.bytes();
+    while let Some(b) = bytes.next() {
+        if b == b'%' {
+            let high = char::from(bytes.next()?).to_digit(16)?;
+            let low = char::from(bytes.next()?).to_digit(16)?;
+            decoded.push((high * 16 + low) as u8);
+        } else {
+            decoded.push(b);
+        }
+    }
+
+    Some(decoded)
 }
 
-/// Parsed `Content-Disposition` and `Content-Type` headers.
+/// The headers which are parsed into the fields of [`Headers`]
+const MODELED_HEADERS: [&str; 4] = [
+    "content-disposition",
+    "content-type",
+    "content-length",
+    "content-id",
+];
+
+/// Options for [`RawHeaders::parse_with`] and [`RawHeaders::parse_zero_copy_with`].
+///
+/// ```rust
+/// use multiparty::headers::ParseOptions;
+///
+/// let options = ParseOptions {
+///     decode_filename_percent: true,
+///     ..ParseOptions::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Percent-decode the `filename` parameter, like some browsers expect.
+    ///
+    /// Off by default, as `%` is allowed in filenames. If decoding fails
+    /// the filename is left untouched. The `filename*` parameter is
+    /// always decoded.
+    pub decode_filename_percent: bool,
+    /// Collect every other header into [`Headers::extra`].
+    ///
+    /// Off by default, to avoid allocating for headers nobody looks at.
+    pub collect_extra: bool,
+    /// Decode a `Content-Disposition` header which isn't valid UTF-8 from this encoding.
+    ///
+    /// Legacy forms may send the `name` and `filename` parameters in the
+    /// encoding of the page, often indicated by a `_charset_` field, which can
+    /// be looked up through [`Encoding::for_label`]. The whole header is decoded,
+    /// so the encoding must be ASCII compatible. `None` by default, requiring UTF-8.
+    #[cfg(feature = "encoding")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "encoding")))]
+    pub encoding: Option<&'static Encoding>,
+}
+
+/// Parsed `Content-Disposition`, `Content-Type`, `Content-Length` and `Content-ID` headers.
 #[derive(Debug, Clone, PartialEq)]
 #[non_exhaustive]
 pub struct Headers {
+    /// The type of the `Content-Disposition` header.
+    pub disposition: DispositionType,
     /// The `name` parameter of the `Content-Disposition` header.
+    ///
+    /// If the `name*` parameter is present, this is its decoded value instead.
+    /// Empty for `attachment` and `inline` dispositions without a `name`.
     pub name: String,
     /// The optional `filename` parameter of the `Content-Disposition` header.
+    ///
+    /// If the `filename*` parameter is present, this is its decoded value instead.
     pub filename: Option<String>,
     /// The value of the optional `Content-Type` header.
     pub content_type: Option<String>,
+    /// The value of the optional `Content-Length` header.
+    ///
+    /// `None` if the header is missing or malformed, see [`RawHeaders::content_length`].
+    pub content_length: Option<u64>,
+    /// The value of the optional `Content-ID` header.
+    ///
+    /// `None` if the header is missing or isn't valid UTF-8, see [`RawHeaders::content_id`].
+    pub content_id: Option<String>,
+    /// The name and the value of every other header, in the order they appear.
+    ///
+    /// Only collected if [`ParseOptions::collect_extra`] is set.
+    /// Invalid UTF-8 is replaced with `U+FFFD`.
+    pub extra: Vec<(String, String)>,
+}
+
+impl Headers {
+    /// Whether the part is a file, meaning that it has a `filename`.
+    pub fn is_file(&self) -> bool {
+        self.filename.is_some()
+    }
+
+    /// Whether the part is a plain field, meaning that it doesn't have a `filename`.
+    pub fn is_field(&self) -> bool {
+        !self.is_file()
+    }
+}
+
+impl Display for Headers {
+    /// Format a short summary of the headers, like
+    /// `name="foo" filename="bar.txt" content-type=text/plain`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "name={:?}", self.name)?;
+        if let Some(filename) = &self.filename {
+            write!(f, " filename={:?}", filename)?;
+        }
+        if let Some(content_type) = &self.content_type {
+            write!(f, " content-type={}", content_type)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parsed `Content-Disposition`, `Content-Type`, `Content-Length` and `Content-ID` headers,
+/// borrowing from [`RawHeaders`].
+///
+/// Returned by [`RawHeaders::parse_zero_copy`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct HeadersRef<'a> {
+    /// The type of the `Content-Disposition` header.
+    pub disposition: DispositionType,
+    /// The `name` parameter of the `Content-Disposition` header.
+    ///
+    /// If the `name*` parameter is present, this is its decoded value instead.
+    /// Empty for `attachment` and `inline` dispositions without a `name`.
+    pub name: Cow<'a, str>,
+    /// The optional `filename` parameter of the `Content-Disposition` header.
+    ///
+    /// If the `filename*` parameter is present, this is its decoded value instead.
+    pub filename: Option<Cow<'a, str>>,
+    /// The value of the optional `Content-Type` header.
+    pub content_type: Option<&'a str>,
+    /// The value of the optional `Content-Length` header.
+    ///
+    /// `None` if the header is missing or malformed, see [`RawHeaders::content_length`].
+    pub content_length: Option<u64>,
+    /// The value of the optional `Content-ID` header.
+    ///
+    /// `None` if the header is missing or isn't valid UTF-8, see [`RawHeaders::content_id`].
+    pub content_id: Option<&'a str>,
+    /// The name and the value of every other header, in the order they appear.
+    ///
+    /// Only collected if [`ParseOptions::collect_extra`] is set.
+    /// Invalid UTF-8 is replaced with `U+FFFD`.
+    pub extra: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+}
+
+impl HeadersRef<'_> {
+    /// Whether the part is a file, meaning that it has a `filename`.
+    pub fn is_file(&self) -> bool {
+        self.filename.is_some()
+    }
+
+    /// Whether the part is a plain field, meaning that it doesn't have a `filename`.
+    pub fn is_field(&self) -> bool {
+        !self.is_file()
+    }
+
+    /// Convert into [`Headers`], copying the borrowed values.
+    pub fn into_owned(self) -> Headers {
+        Headers {
+            disposition: self.disposition,
+            name: self.name.into_owned(),
+            filename: self.filename.map(Cow::into_owned),
+            content_type: self.content_type.map(str::to_owned),
+            content_length: self.content_length,
+            content_id: self.content_id.map(str::to_owned),
+            extra: self
+                .extra
+                .into_iter()
+                .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                .collect(),
+        }
+    }
+}
+
+/// A parsed `Content-Type` header, like `text/plain; charset=utf-8`.
+///
+/// Returned by [`RawHeaders::content_type`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ContentType {
+    /// The media type, like `text/plain`.
+    pub mime: String,
+    /// The parameters following the media type, in the order they appear.
+    ///
+    /// Quoted values are unquoted. Parameters without a value are skipped.
+    pub params: Vec<(String, String)>,
+}
+
+impl ContentType {
+    /// Parse the value of a `Content-Type` header.
+    pub fn parse(value: &str) -> Self {
+        let mime = value.split(';').next().expect("always Some").trim();
+        let params = params(value)
+            .filter_map(|(name, value)| {
+                value.map(|value| (name.to_string(), unquote(value).into_owned()))
+            })
+            .collect();
+
+        Self {
+            mime: mime.to_owned(),
+            params,
+        }
+    }
+
+    /// Get the value of the first parameter named `name`, ignoring ASCII case.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(name_, _value)| name_.eq_ignore_ascii_case(name))
+            .map(|(_name, value)| value.as_str())
+    }
+
+    /// The `charset` parameter.
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    /// The `boundary` parameter, used by nested `multipart/*` bodies.
+    pub fn boundary(&self) -> Option<&str> {
+        self.param("boundary")
+    }
 }
 
-/// Error encountered while parsing the `Content-Disposition` and `Content-Type` headers.
-#[derive(Debug, PartialEq)]
+/// The type of a `Content-Disposition` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DispositionType {
+    /// `form-data`, used by `multipart/form-data` bodies.
+    FormData,
+    /// `attachment`
+    Attachment,
+    /// `inline`
+    Inline,
+}
+
+/// Error encountered while parsing headers.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Error(InnerError);
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 enum InnerError {
     ContentDispositionNotFound,
     ContentDispositionUtf8,
-    ContentDispositionNotFormData,
+    #[cfg(feature = "encoding")]
+    ContentDispositionEncoding(&'static str),
+    UnknownContentDispositionType,
     InvalidContentDispositionParam,
     NoContentDispositionName,
+    InvalidExtendedName,
+    InvalidExtendedFilename,
+    UnterminatedQuotedString,
     ContentTypeUtf8,
+    InvalidContentLength,
+    ContentIdUtf8,
+    #[cfg(feature = "server")]
+    Headers(httparse::Error),
+    #[cfg(feature = "server")]
+    IncompleteHeaders,
 }
 
 impl Display for Error {
@@ -131,16 +847,38 @@ impl Display for Error {
             InnerError::ContentDispositionUtf8 => {
                 f.write_str("Content-Disposition header isn't valid utf-8")
             }
-            InnerError::ContentDispositionNotFormData => {
-                f.write_str("Content-Disposition doesn't begin with 'form-data'")
+            #[cfg(feature = "encoding")]
+            InnerError::ContentDispositionEncoding(encoding) => {
+                write!(f, "Content-Disposition header isn't valid {}", encoding)
             }
+            InnerError::UnknownContentDispositionType => f.write_str(
+                "Content-Disposition type isn't one of 'form-data', 'attachment' or 'inline'",
+            ),
             InnerError::InvalidContentDispositionParam => {
                 f.write_str("Invalid Content-Disposition parameter")
             }
             InnerError::NoContentDispositionName => {
                 f.write_str("Content-Disposition is missing the name parameter")
             }
+            InnerError::InvalidExtendedName => {
+                f.write_str("Invalid Content-Disposition name* parameter")
+            }
+            InnerError::InvalidExtendedFilename => {
+                f.write_str("Invalid Content-Disposition filename* parameter")
+            }
+            InnerError::UnterminatedQuotedString => f.write_str(
+                "Content-Disposition parameter has an unterminated quoted string, \
+                 possibly because it contains an unencoded line break",
+            ),
             InnerError::ContentTypeUtf8 => f.write_str("Content-Type header isn't valid utf-8"),
+            InnerError::InvalidContentLength => f.write_str("Invalid Content-Length header"),
+            InnerError::ContentIdUtf8 => f.write_str("Content-ID header isn't valid utf-8"),
+            #[cfg(feature = "server")]
+            InnerError::Headers(err) => write!(f, "Invalid headers: {}", err),
+            #[cfg(feature = "server")]
+            InnerError::IncompleteHeaders => {
+                f.write_str("Headers aren't terminated by an empty line")
+            }
         }
     }
 }
@@ -153,6 +891,420 @@ mod tests {
 
     use super::*;
 
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn encoding() {
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"caf\xe9\"; filename=\"\xe0.txt\""),
+        )]);
+        assert_eq!(
+            headers.parse(),
+            Err(Error(InnerError::ContentDispositionUtf8))
+        );
+
+        let options = ParseOptions {
+            encoding: Some(encoding_rs::WINDOWS_1252),
+            ..ParseOptions::default()
+        };
+        let parsed = headers.parse_with(options.clone()).unwrap();
+        assert_eq!(parsed.name, "caf\u{e9}");
+        assert_eq!(parsed.filename.as_deref(), Some("\u{e0}.txt"));
+
+        // UTF-8 headers aren't decoded from `encoding`
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from("form-data; name=\"caf\u{e9}\""),
+        )]);
+        assert_eq!(headers.parse_with(options).unwrap().name, "caf\u{e9}");
+
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"\xff\""),
+        )]);
+        let options = ParseOptions {
+            encoding: Some(encoding_rs::SHIFT_JIS),
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            headers.parse_with(options),
+            Err(Error(InnerError::ContentDispositionEncoding("Shift_JIS")))
+        );
+    }
+
+    #[test]
+    fn decode_filename_percent() {
+        fn parse(filename: &'static str, decode_filename_percent: bool) -> Option<String> {
+            let headers = RawHeaders::new(vec![(
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from(format!(
+                    "form-data; name=\"foo\"; filename=\"{}\"",
+                    filename
+                )),
+            )]);
+            let options = ParseOptions {
+                decode_filename_percent,
+                ..ParseOptions::default()
+            };
+            headers.parse_with(options).unwrap().filename
+        }
+
+        assert_eq!(
+            parse("foo%20bar.txt", false).as_deref(),
+            Some("foo%20bar.txt")
+        );
+        assert_eq!(parse("foo%20bar.txt", true).as_deref(), Some("foo bar.txt"));
+        assert_eq!(
+            parse("%E2%82%AC.txt", true).as_deref(),
+            Some("\u{20ac}.txt")
+        );
+        assert_eq!(parse("100%.txt", true).as_deref(), Some("100%.txt"));
+        assert_eq!(parse("%FF.txt", true).as_deref(), Some("%FF.txt"));
+    }
+
+    #[test]
+    fn content_disposition_raw() {
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"foo\"; x-custom=1"),
+        )]);
+        let raw = "form-data; name=\"foo\"; x-custom=1";
+        assert_eq!(headers.content_disposition_raw(), Some(raw.as_bytes()));
+        assert_eq!(headers.content_disposition_str(), Ok(Some(raw)));
+
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"\xFF\""),
+        )]);
+        assert!(headers.content_disposition_raw().is_some());
+        assert!(headers.content_disposition_str().is_err());
+
+        let headers = RawHeaders::new(Vec::new());
+        assert_eq!(headers.content_disposition_raw(), None);
+        assert_eq!(headers.content_disposition_str(), Ok(None));
+    }
+
+    #[test]
+    fn header_name_whitespace() {
+        let headers = RawHeaders::new(vec![
+            (
+                Bytes::from_static(b"Content-Disposition "),
+                Bytes::from_static(b"form-data; name=\"foo\""),
+            ),
+            (
+                Bytes::from_static(b"\tcontent-type  "),
+                Bytes::from_static(b"text/plain"),
+            ),
+        ]);
+
+        assert!(headers.get("content-disposition").is_some());
+        assert!(headers.get("CONTENT-TYPE").is_some());
+        let options = ParseOptions {
+            collect_extra: true,
+            ..ParseOptions::default()
+        };
+        assert!(headers.parse_with(options).unwrap().extra.is_empty());
+        let parsed = headers.parse().unwrap();
+        assert_eq!(parsed.name, "foo");
+        assert_eq!(parsed.content_type.as_deref(), Some("text/plain"));
+
+        assert_eq!(trim_whitespace(b"  "), b"");
+        assert_eq!(trim_whitespace(b""), b"");
+        assert_eq!(trim_whitespace(b" a b "), b"a b");
+    }
+
+    #[test]
+    fn get_str_invalid_utf8() {
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"X-Foo"),
+            Bytes::from_static(b"\xff"),
+        )]);
+
+        assert!(matches!(headers.get_str("x-foo"), Some(Err(_))));
+    }
+
+    #[test]
+    fn duplicate_headers() {
+        let headers = RawHeaders::new(vec![
+            (
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(b"form-data; name=\"foo\""),
+            ),
+            (
+                Bytes::from_static(b"Content-Type"),
+                Bytes::from_static(b"text/plain"),
+            ),
+            (
+                Bytes::from_static(b"content-type"),
+                Bytes::from_static(b"application/json"),
+            ),
+        ]);
+
+        assert_eq!(
+            headers.get_all("Content-Type").collect::<Vec<_>>(),
+            ["text/plain", "application/json"]
+        );
+        assert_eq!(headers.get_all("x-missing").count(), 0);
+        assert_eq!(headers.get("content-type").unwrap(), "text/plain");
+        assert_eq!(headers.get_str("content-type"), Some(Ok("text/plain")));
+        assert_eq!(headers.get_str("x-missing"), None);
+        // The first one wins
+        assert_eq!(
+            headers.parse().unwrap().content_type.as_deref(),
+            Some("text/plain")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "server")]
+    fn from_bytes() {
+        let block = Bytes::from_static(
+            b"Content-Disposition: form-data; name=\"foo\"\r\nX-Foo: bar\r\n\r\nbody",
+        );
+        let headers = RawHeaders::from_bytes(&block).unwrap();
+        assert_eq!(headers.parse().unwrap().name, "foo");
+        assert_eq!(headers.get("x-foo").unwrap(), "bar");
+        assert_eq!(headers.as_bytes(), &block[..block.len() - "body".len()]);
+
+        assert_eq!(
+            RawHeaders::from_bytes(&Bytes::from_static(b"X-Foo: bar\r\n")).unwrap_err(),
+            Error(InnerError::IncompleteHeaders)
+        );
+        assert_eq!(
+            RawHeaders::from_bytes(&Bytes::from_static(b"X Foo: bar\r\n\r\n")).unwrap_err(),
+            Error(InnerError::Headers(httparse::Error::HeaderName))
+        );
+
+        let block = Bytes::from_static(b"Content-Disposition : form-data; name=\"foo\"\r\n\r\n");
+        let headers = RawHeaders::from_bytes(&block).unwrap();
+        assert_eq!(headers.parse().unwrap().name, "foo");
+    }
+
+    #[test]
+    fn unterminated_quoted_string() {
+        fn parse(content_disposition: &'static [u8]) -> Result<Headers, Error> {
+            RawHeaders::new(vec![(
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(content_disposition),
+            )])
+            .parse()
+        }
+
+        // A filename containing an unencoded `\r\n`, split by the headers decoder
+        let err = parse(b"form-data; name=\"foo\"; filename=\"a").unwrap_err();
+        assert_eq!(err, Error(InnerError::UnterminatedQuotedString));
+        assert!(err.to_string().contains("line break"));
+        assert_eq!(
+            parse(b"form-data; name=\"foo").unwrap_err(),
+            Error(InnerError::UnterminatedQuotedString)
+        );
+
+        let headers = parse(b"form-data; name=\"foo\"; filename=\"C:\\dir\\a\\\"").unwrap();
+        assert_eq!(headers.filename.as_deref(), Some("C:\\dir\\a\""));
+    }
+
+    #[test]
+    fn extra() {
+        let headers = RawHeaders::new(vec![
+            (
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(b"form-data; name=\"foo\"; filename=\"foo.txt\""),
+            ),
+            (
+                Bytes::from_static(b"content-type"),
+                Bytes::from_static(b"text/plain"),
+            ),
+            (
+                Bytes::from_static(b"X-File-Hash"),
+                Bytes::from_static(b"abcd"),
+            ),
+            (
+                Bytes::from_static(b"X-Invalid"),
+                Bytes::from_static(b"a\xffb"),
+            ),
+        ]);
+
+        assert!(headers.parse().unwrap().extra.is_empty());
+
+        let options = ParseOptions {
+            collect_extra: true,
+            ..ParseOptions::default()
+        };
+        let extra = [
+            ("X-File-Hash".to_owned(), "abcd".to_owned()),
+            ("X-Invalid".to_owned(), "a\u{FFFD}b".to_owned()),
+        ];
+        assert_eq!(headers.parse_with(options.clone()).unwrap().extra, extra);
+        assert_eq!(
+            headers.parse_zero_copy_with(options).unwrap().extra,
+            [
+                (Cow::Borrowed("X-File-Hash"), Cow::Borrowed("abcd")),
+                (Cow::Borrowed("X-Invalid"), Cow::Borrowed("a\u{FFFD}b")),
+            ]
+        );
+    }
+
+    #[test]
+    fn is_file() {
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"foo\"; filename=\"\""),
+        )]);
+        assert!(headers.parse().unwrap().is_file());
+        assert!(!headers.parse_zero_copy().unwrap().is_field());
+
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"foo\""),
+        )]);
+        assert!(headers.parse().unwrap().is_field());
+        assert!(!headers.parse_zero_copy().unwrap().is_file());
+    }
+
+    #[test]
+    fn display() {
+        let headers = RawHeaders::new(vec![
+            (
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(b"form-data; name=\"foo\"; filename=\"bar \\\"1\\\".txt\""),
+            ),
+            (
+                Bytes::from_static(b"Content-Type"),
+                Bytes::from_static(b"text/plain"),
+            ),
+        ]);
+        assert_eq!(
+            headers.parse().unwrap().to_string(),
+            r#"name="foo" filename="bar \"1\".txt" content-type=text/plain"#
+        );
+
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"foo\""),
+        )]);
+        assert_eq!(headers.parse().unwrap().to_string(), r#"name="foo""#);
+    }
+
+    #[test]
+    fn content_id() {
+        let headers = RawHeaders::new(vec![
+            (
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(b"form-data; name=\"abcd\""),
+            ),
+            (
+                Bytes::from_static(b"Content-ID"),
+                Bytes::from_static(b" <root.message@example.com> "),
+            ),
+        ]);
+        assert_eq!(headers.content_id(), Ok(Some("<root.message@example.com>")));
+        let options = ParseOptions {
+            collect_extra: true,
+            ..ParseOptions::default()
+        };
+        let parsed = headers.parse_with(options).unwrap();
+        assert_eq!(
+            parsed.content_id.as_deref(),
+            Some("<root.message@example.com>")
+        );
+        assert!(parsed.extra.is_empty());
+
+        // `multipart/related` parts usually don't have a `Content-Disposition`
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"content-id"),
+            Bytes::from_static(b"<part1>"),
+        )]);
+        assert_eq!(headers.content_id(), Ok(Some("<part1>")));
+        assert!(headers.parse().is_err());
+
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-ID"),
+            Bytes::from_static(b"<\xff>"),
+        )]);
+        assert_eq!(headers.content_id(), Err(Error(InnerError::ContentIdUtf8)));
+        let headers = RawHeaders::new(vec![
+            (
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(b"form-data; name=\"abcd\""),
+            ),
+            (
+                Bytes::from_static(b"Content-ID"),
+                Bytes::from_static(b"<\xff>"),
+            ),
+        ]);
+        let parsed = headers.parse().unwrap();
+        assert_eq!(parsed.name, "abcd");
+        assert_eq!(parsed.content_id, None);
+        assert_eq!(RawHeaders::new(Vec::new()).content_id(), Ok(None));
+    }
+
+    #[test]
+    fn content_length() {
+        fn headers(content_length: &'static str) -> RawHeaders {
+            RawHeaders::new(vec![
+                (
+                    Bytes::from_static(b"Content-Disposition"),
+                    Bytes::from_static(b"form-data; name=\"abcd\""),
+                ),
+                (
+                    Bytes::from_static(b"Content-Length"),
+                    Bytes::from_static(content_length.as_bytes()),
+                ),
+            ])
+        }
+
+        assert_eq!(headers("1234").content_length(), Ok(Some(1234)));
+        assert_eq!(headers(" 0 ").parse().unwrap().content_length, Some(0));
+        assert!(headers("").content_length().is_err());
+        assert!(headers("+12").content_length().is_err());
+        assert!(headers("12a").content_length().is_err());
+        assert!(headers("99999999999999999999999").content_length().is_err());
+        // A malformed Content-Length doesn't prevent parsing the other headers
+        let parsed = headers("12abc").parse().unwrap();
+        assert_eq!(parsed.name, "abcd");
+        assert_eq!(parsed.content_length, None);
+
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"abcd\""),
+        )]);
+        assert_eq!(headers.content_length(), Ok(None));
+    }
+
+    #[test]
+    fn content_type() {
+        let headers = RawHeaders::new(vec![
+            (
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(b"form-data; name=\"abcd\""),
+            ),
+            (
+                Bytes::from_static(b"Content-Type"),
+                Bytes::from_static(b"text/plain ; CharSet=\"utf-8\"; format=flowed; x"),
+            ),
+        ]);
+
+        let content_type = headers.content_type().unwrap().unwrap();
+        assert_eq!(content_type.mime, "text/plain");
+        assert_eq!(
+            content_type.params,
+            vec![
+                ("CharSet".to_owned(), "utf-8".to_owned()),
+                ("format".to_owned(), "flowed".to_owned())
+            ]
+        );
+        assert_eq!(content_type.charset(), Some("utf-8"));
+        assert_eq!(content_type.boundary(), None);
+
+        let content_type = ContentType::parse("multipart/mixed; boundary=\"a;b\"");
+        assert_eq!(content_type.mime, "multipart/mixed");
+        assert_eq!(content_type.boundary(), Some("a;b"));
+        assert_eq!(content_type.charset(), None);
+
+        let headers = RawHeaders::new(Vec::new());
+        assert_eq!(headers.content_type(), Ok(None));
+    }
+
     #[test]
     fn ascii() {
         let headers = vec![
@@ -173,6 +1325,55 @@ mod tests {
         assert_eq!(parsed.content_type.as_deref(), Some("text/plain"));
     }
 
+    #[test]
+    fn zero_copy() {
+        let headers = vec![
+            (
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(b"form-data; name=\"abcd\"; filename=\"a\\\"b.txt\""),
+            ),
+            (
+                Bytes::from_static(b"Content-Type"),
+                Bytes::from_static(b"text/plain"),
+            ),
+        ];
+        let headers = RawHeaders::new(headers);
+
+        let parsed = headers.parse_zero_copy().unwrap();
+        assert!(matches!(parsed.name, Cow::Borrowed("abcd")));
+        assert!(
+            matches!(parsed.filename, Some(Cow::Owned(ref filename)) if filename == "a\"b.txt")
+        );
+        assert_eq!(parsed.content_type, Some("text/plain"));
+        assert_eq!(parsed.into_owned(), headers.parse().unwrap());
+    }
+
+    #[test]
+    fn name_filename() {
+        let headers = RawHeaders::new(vec![
+            (
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(b"form-data; name=\"abcd\"; filename=\"test.txt\""),
+            ),
+            (
+                Bytes::from_static(b"Content-Type"),
+                Bytes::from_static(b"\xff"),
+            ),
+        ]);
+        assert_eq!(headers.name().unwrap(), "abcd");
+        assert_eq!(headers.filename().unwrap().as_deref(), Some("test.txt"));
+        assert_eq!(headers.parse(), Err(Error(InnerError::ContentTypeUtf8)));
+
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; filename=\"test.txt\""),
+        )]);
+        assert_eq!(
+            headers.name(),
+            Err(Error(InnerError::NoContentDispositionName))
+        );
+    }
+
     #[test]
     fn ascii_no_cd() {
         let headers = vec![(
@@ -211,7 +1412,44 @@ mod tests {
 
         assert_eq!(
             headers.parse(),
-            Err(Error(InnerError::ContentDispositionNotFormData))
+            Err(Error(InnerError::UnknownContentDispositionType))
+        );
+    }
+
+    #[test]
+    fn disposition_types() {
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"abcd\""),
+        )]);
+        let parsed = headers.parse().unwrap();
+        assert_eq!(parsed.disposition, DispositionType::FormData);
+
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"Attachment; filename=\"test.txt\""),
+        )]);
+        let parsed = headers.parse().unwrap();
+        assert_eq!(parsed.disposition, DispositionType::Attachment);
+        assert_eq!(parsed.name, "");
+        assert_eq!(parsed.filename.as_deref(), Some("test.txt"));
+
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"inline"),
+        )]);
+        let parsed = headers.parse().unwrap();
+        assert_eq!(parsed.disposition, DispositionType::Inline);
+        assert_eq!(parsed.name, "");
+        assert!(parsed.filename.is_none());
+
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-datax; name=\"abcd\""),
+        )]);
+        assert_eq!(
+            headers.parse(),
+            Err(Error(InnerError::UnknownContentDispositionType))
         );
     }
 
@@ -257,6 +1495,186 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ext_name() {
+        for value in &[
+            &b"form-data; name*=UTF-8''%E2%82%AC%20rates"[..],
+            b"form-data; name=\"rates\"; name*=UTF-8''%E2%82%AC%20rates",
+            b"form-data; name*=UTF-8''%E2%82%AC%20rates; name=\"rates\"",
+        ] {
+            let headers = RawHeaders::new(vec![(
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(value),
+            )]);
+
+            assert_eq!(headers.parse().unwrap().name, "\u{20ac} rates");
+            assert_eq!(headers.name().unwrap(), "\u{20ac} rates");
+        }
+
+        let headers = RawHeaders::new(vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"abcd\"; name*=UTF-8''%ZZ"),
+        )]);
+        assert_eq!(headers.parse(), Err(Error(InnerError::InvalidExtendedName)));
+    }
+
+    #[test]
+    fn ext_filename() {
+        let headers = vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"abcd\"; filename*=UTF-8''%E2%82%AC%20rates.txt"),
+        )];
+        let headers = RawHeaders::new(headers);
+
+        let parsed = headers.parse().unwrap();
+        assert_eq!(parsed.name, "abcd");
+        assert_eq!(parsed.filename.as_deref(), Some("\u{20ac} rates.txt"));
+    }
+
+    #[test]
+    fn ext_filename_precedence() {
+        let headers = vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(
+                b"form-data; name=\"abcd\"; filename*=iso-8859-1'en'%A3%20rates.txt; filename=\"rates.txt\"",
+            ),
+        )];
+        let headers = RawHeaders::new(headers);
+
+        let parsed = headers.parse().unwrap();
+        assert_eq!(parsed.name, "abcd");
+        assert_eq!(parsed.filename.as_deref(), Some("\u{a3} rates.txt"));
+    }
+
+    #[test]
+    fn ext_filename_invalid() {
+        for value in &[
+            &b"form-data; name=\"abcd\"; filename*=UTF-8''%E2%82"[..],
+            b"form-data; name=\"abcd\"; filename*=UTF-8''%ZZ",
+            b"form-data; name=\"abcd\"; filename*=%E2%82%AC",
+            b"form-data; name=\"abcd\"; filename*=klingon''abcd",
+        ] {
+            let headers = vec![(
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(value),
+            )];
+            let headers = RawHeaders::new(headers);
+
+            assert_eq!(
+                headers.parse(),
+                Err(Error(InnerError::InvalidExtendedFilename))
+            );
+        }
+    }
+
+    #[test]
+    fn escaped_quotes() {
+        let headers = vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"ab\\\\cd\"; filename=\"a\\\"b.txt\""),
+        )];
+        let headers = RawHeaders::new(headers);
+
+        let parsed = headers.parse().unwrap();
+        assert_eq!(parsed.name, "ab\\cd");
+        assert_eq!(parsed.filename.as_deref(), Some("a\"b.txt"));
+    }
+
+    #[test]
+    fn quoted_semicolon() {
+        let headers = vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"ab;cd\"; filename=\"a\\\";b.txt\"; size=1"),
+        )];
+        let headers = RawHeaders::new(headers);
+
+        let parsed = headers.parse().unwrap();
+        assert_eq!(parsed.name, "ab;cd");
+        assert_eq!(parsed.filename.as_deref(), Some("a\";b.txt"));
+
+        let params = headers.content_disposition_params().unwrap();
+        assert_eq!(params.len(), 3);
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(
+            split_unquoted("a;b;c", ';').collect::<Vec<_>>(),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(
+            split_unquoted("a;\"b;c\";d", ';').collect::<Vec<_>>(),
+            vec!["a", "\"b;c\"", "d"]
+        );
+        assert_eq!(
+            split_unquoted("a;\"b\\\";c\";", ';').collect::<Vec<_>>(),
+            vec!["a", "\"b\\\";c\"", ""]
+        );
+        assert_eq!(split_unquoted("", ';').collect::<Vec<_>>(), vec![""]);
+    }
+
+    #[test]
+    fn windows_path() {
+        let headers = vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(b"form-data; name=\"abcd\"; filename=\"C:\\docs\\test.txt\""),
+        )];
+        let headers = RawHeaders::new(headers);
+
+        let parsed = headers.parse().unwrap();
+        assert_eq!(parsed.name, "abcd");
+        assert_eq!(parsed.filename.as_deref(), Some("C:\\docs\\test.txt"));
+    }
+
+    #[test]
+    fn cd_params() {
+        let headers = vec![(
+            Bytes::from_static(b"Content-Disposition"),
+            Bytes::from_static(
+                b"form-data; name=\"abcd\"; something; creation-date=\"Wed, 12 Feb 1997 16:29:51 -0500\"; size=1234",
+            ),
+        )];
+        let headers = RawHeaders::new(headers);
+
+        let params = headers.content_disposition_params().unwrap();
+        assert_eq!(
+            params,
+            vec![
+                ("name".to_string(), "abcd".to_string()),
+                (
+                    "creation-date".to_string(),
+                    "Wed, 12 Feb 1997 16:29:51 -0500".to_string()
+                ),
+                ("size".to_string(), "1234".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn iter_get() {
+        let headers = vec![
+            (
+                Bytes::from_static(b"Content-Disposition"),
+                Bytes::from_static(b"form-data; name=\"abcd\""),
+            ),
+            (
+                Bytes::from_static(b"X-Checksum"),
+                Bytes::from_static(b"1234"),
+            ),
+        ];
+        let headers = RawHeaders::new(headers);
+
+        let names = headers
+            .iter()
+            .map(|(name, _value)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Content-Disposition", "X-Checksum"]);
+
+        assert_eq!(headers.get("x-checksum").unwrap(), "1234");
+        assert_eq!(headers.get("X-CHECKSUM").unwrap(), "1234");
+        assert!(headers.get("Content-Type").is_none());
+    }
+
     #[test]
     fn no_cd() {
         let headers = vec![(
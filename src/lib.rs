@@ -2,7 +2,7 @@
 //!
 //! Simple zero copy* streaming multipart decoder implementation.
 //!
-//! \* Except for streams yielding `Bytes` smaller than half the boundary length.
+//! \* Except for part headers split across multiple `Bytes`.
 //!
 //! ## Examples
 //!
@@ -18,7 +18,7 @@
 //!
 //! # if false {
 //! let boundary = todo!("A multipart/form-data boundary");
-//! let stream = todo!("A Stream<Item = std::io::Result<Bytes>> + Unpin");
+//! let stream = todo!("A Stream<Item = std::io::Result<Bytes>>");
 //! # }
 //! # let boundary = "abcd";
 //! # let content = "--abcd\r\ncontent-type: text/plain\r\ncontent-disposition: form-data; name=\"foo\"; filename=\"test.txt\"\r\n\r\nbar\r\n--abcd--";
@@ -48,13 +48,22 @@
 #![forbid(unsafe_code)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![deny(missing_docs, missing_debug_implementations)]
+// Only the integrations need the MSRV, the decoder sticks to older std APIs
+#![allow(clippy::io_other_error, clippy::unnecessary_map_or)]
 
-#[cfg(not(feature = "server"))]
-compile_error!("This version requires the `server` feature on");
+#[cfg(not(any(feature = "server", feature = "client")))]
+compile_error!("This version requires the `server` or the `client` feature on");
 
-mod boundary;
+pub mod boundary;
+#[cfg(feature = "client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "client")))]
+pub mod client;
 pub mod headers;
 #[cfg(feature = "server")]
 #[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+pub mod search;
+#[cfg(feature = "server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
 pub mod server;
+#[cfg(feature = "server")]
 mod utils;
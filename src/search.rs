@@ -0,0 +1,352 @@
+//! Search primitives for byte slices, including slices split across multiple buffers.
+//!
+//! These are the building blocks used by the [`server`] decoders to find the
+//! boundary without joining the [`Bytes`] they've been given.
+//!
+//! [`server`]: crate::server
+//! [`Bytes`]: bytes::Bytes
+
+use std::iter;
+
+/// Search for the first occurrence of `needle` in `haystack`.
+///
+/// Returns the index in `haystack` at which `needle` starts.
+///
+/// ```
+/// use multiparty::search::find_bytes;
+///
+/// assert_eq!(find_bytes(b"abcdefgh", b"cde"), Some(2));
+/// assert_eq!(find_bytes(b"abcdefgh", b"xyz"), None);
+/// ```
+pub fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    memchr::memmem::find(haystack, needle)
+}
+
+/// Search for the first occurrence of `needle` in `haystack`, ignoring ASCII case.
+///
+/// Returns the index in `haystack` at which `needle` starts.
+///
+/// ```
+/// use multiparty::search::find_bytes_ignore_ascii_case;
+///
+/// assert_eq!(find_bytes_ignore_ascii_case(b"abcDEFgh", b"CdE"), Some(2));
+/// assert_eq!(find_bytes_ignore_ascii_case(b"abcdefgh", b"xyz"), None);
+/// ```
+pub fn find_bytes_ignore_ascii_case(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let first = match needle.first() {
+        Some(&first) => first,
+        None => return Some(0),
+    };
+
+    memchr::memchr2_iter(
+        first.to_ascii_lowercase(),
+        first.to_ascii_uppercase(),
+        haystack,
+    )
+    .find(|&i| {
+        haystack.len() - i >= needle.len()
+            && haystack[i..i + needle.len()].eq_ignore_ascii_case(needle)
+    })
+}
+
+/// Search for the first occurrence of `needle` in `haystack1` followed by `haystack2`,
+/// as if they were a single contiguous slice.
+///
+/// Only occurrences *starting* in `haystack1` are returned. `needle` may either be
+/// fully contained in `haystack1` or cross over into `haystack2`, but occurrences
+/// fully contained in `haystack2` are ignored. The returned index is relative to
+/// the start of `haystack1`, and so it's always smaller than `haystack1.len()`.
+///
+/// ```
+/// use multiparty::search::find_bytes_split;
+///
+/// assert_eq!(find_bytes_split(b"abcd", b"efgh", b"bc"), Some(1));
+/// assert_eq!(find_bytes_split(b"abcd", b"efgh", b"cdef"), Some(2));
+/// // starts in `haystack2`
+/// assert_eq!(find_bytes_split(b"abcd", b"efgh", b"fg"), None);
+/// ```
+pub fn find_bytes_split(haystack1: &[u8], haystack2: &[u8], needle: &[u8]) -> Option<usize> {
+    find_bytes_chain(haystack1, iter::once(haystack2), needle)
+}
+
+/// Search for the first occurrence of `needle` in `haystack1` followed by every
+/// haystack in `rest`, as if they were a single contiguous slice.
+///
+/// Like [`find_bytes_split`], only occurrences *starting* in `haystack1` are
+/// returned, and the returned index is relative to the start of `haystack1`.
+/// `rest` is only iterated for as many bytes as `needle` could span.
+///
+/// ```
+/// use multiparty::search::find_bytes_chain;
+///
+/// let rest: &[&[u8]] = &[b"c", b"", b"de", b"f"];
+/// assert_eq!(find_bytes_chain(b"ab", rest.iter().copied(), b"bcdef"), Some(1));
+/// ```
+pub fn find_bytes_chain<'a, I>(haystack1: &'a [u8], rest: I, needle: &[u8]) -> Option<usize>
+where
+    I: Iterator<Item = &'a [u8]> + Clone,
+{
+    find_bytes_chain_with(haystack1, rest, needle, find_bytes, |a, b| a == b)
+}
+
+/// Like [`find_bytes_chain`], but ignoring ASCII case.
+///
+/// ```
+/// use multiparty::search::find_bytes_chain_ignore_ascii_case;
+///
+/// let rest: &[&[u8]] = &[b"C", b"", b"de", b"F"];
+/// assert_eq!(
+///     find_bytes_chain_ignore_ascii_case(b"aB", rest.iter().copied(), b"bcdef"),
+///     Some(1)
+/// );
+/// ```
+pub fn find_bytes_chain_ignore_ascii_case<'a, I>(
+    haystack1: &'a [u8],
+    rest: I,
+    needle: &[u8],
+) -> Option<usize>
+where
+    I: Iterator<Item = &'a [u8]> + Clone,
+{
+    find_bytes_chain_with(
+        haystack1,
+        rest,
+        needle,
+        find_bytes_ignore_ascii_case,
+        <[u8]>::eq_ignore_ascii_case,
+    )
+}
+
+fn find_bytes_chain_with<'a, I>(
+    haystack1: &'a [u8],
+    rest: I,
+    needle: &[u8],
+    find: fn(&[u8], &[u8]) -> Option<usize>,
+    eq: fn(&[u8], &[u8]) -> bool,
+) -> Option<usize>
+where
+    I: Iterator<Item = &'a [u8]> + Clone,
+{
+    if needle.is_empty() {
+        return if haystack1.is_empty() { None } else { Some(0) };
+    }
+
+    // a match contained in `haystack1` always comes before one crossing into `rest`
+    if let Some(i) = find(haystack1, needle) {
+        return Some(i);
+    }
+
+    // a match crossing into `rest` starts in the last `needle.len() - 1` bytes
+    // of `haystack1`, check each of them without joining the haystacks
+    let start = haystack1.len().saturating_sub(needle.len() - 1);
+    (start..haystack1.len()).find(|&i| {
+        let haystacks = iter::once(&haystack1[i..]).chain(rest.clone());
+        starts_with_chain_with(haystacks, needle, eq)
+    })
+}
+
+/// Determine if the concatenation of `haystacks` starts with `needle`.
+///
+/// ```
+/// use multiparty::search::starts_with_chain;
+///
+/// let haystacks: &[&[u8]] = &[b"a", b"bc", b"", b"d"];
+/// assert!(starts_with_chain(haystacks.iter().copied(), b"abcd"));
+/// assert!(!starts_with_chain(haystacks.iter().copied(), b"abcde"));
+/// ```
+pub fn starts_with_chain<'a>(haystacks: impl Iterator<Item = &'a [u8]>, needle: &[u8]) -> bool {
+    starts_with_chain_with(haystacks, needle, |a, b| a == b)
+}
+
+/// Like [`starts_with_chain`], but ignoring ASCII case.
+///
+/// ```
+/// use multiparty::search::starts_with_chain_ignore_ascii_case;
+///
+/// let haystacks: &[&[u8]] = &[b"a", b"Bc", b"", b"D"];
+/// assert!(starts_with_chain_ignore_ascii_case(haystacks.iter().copied(), b"abcd"));
+/// assert!(!starts_with_chain_ignore_ascii_case(haystacks.iter().copied(), b"abce"));
+/// ```
+pub fn starts_with_chain_ignore_ascii_case<'a>(
+    haystacks: impl Iterator<Item = &'a [u8]>,
+    needle: &[u8],
+) -> bool {
+    starts_with_chain_with(haystacks, needle, <[u8]>::eq_ignore_ascii_case)
+}
+
+fn starts_with_chain_with<'a>(
+    haystacks: impl Iterator<Item = &'a [u8]>,
+    mut needle: &[u8],
+    eq: fn(&[u8], &[u8]) -> bool,
+) -> bool {
+    for haystack in haystacks {
+        if needle.is_empty() {
+            break;
+        }
+
+        let len = haystack.len().min(needle.len());
+        if !eq(&haystack[..len], &needle[..len]) {
+            return false;
+        }
+        needle = &needle[len..];
+    }
+
+    needle.is_empty()
+}
+
+/// Determine if `haystack1` followed by `haystack2` starts with `needle`,
+/// without joining them.
+///
+/// ```
+/// use multiparty::search::starts_with_between;
+///
+/// assert!(starts_with_between(b"\r", b"\n--", b"\r\n"));
+/// assert!(!starts_with_between(b"\r", b"--", b"\r\n"));
+/// ```
+pub fn starts_with_between(haystack1: &[u8], haystack2: &[u8], needle: &[u8]) -> bool {
+    let skip1 = haystack1.len().min(needle.len());
+
+    let (needle1, needle2) = needle.split_at(skip1);
+    &haystack1[..skip1] == needle1 && haystack2.starts_with(needle2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_bytes() {
+        assert_eq!(find_bytes(b"abcdefgh", b"abc"), Some(0));
+        assert_eq!(find_bytes(b"abc", b"abc"), Some(0));
+        assert_eq!(find_bytes(b"abcdefgh", b"bcde"), Some(1));
+        assert_eq!(find_bytes(b"abcdefgh", b"bc"), Some(1));
+    }
+
+    #[test]
+    fn search_bytes_chain() {
+        fn find(haystack1: &[u8], rest: &[&[u8]], needle: &[u8]) -> Option<usize> {
+            find_bytes_chain(haystack1, rest.iter().copied(), needle)
+        }
+
+        assert_eq!(find(b"abcd", &[b"efgh"], b"abc"), Some(0));
+        assert_eq!(find(b"abc", &[b""], b"abc"), Some(0));
+        assert_eq!(find(b"abcd", &[b"efgh"], b"bcde"), Some(1));
+        assert_eq!(find(b"abcd", &[b"efgh"], b"bc"), Some(1));
+        assert_eq!(find(b"abcd", &[b"efgh"], b"fh"), None);
+        assert_eq!(find(b"ab", &[b"c", b"", b"de", b"f"], b"bcdef"), Some(1));
+        assert_eq!(find(b"ab", &[b"c", b"de"], b"bcdef"), None);
+        assert_eq!(find(b"ab", &[b"c", b"de"], b"cde"), None);
+    }
+
+    #[test]
+    fn search_bytes_chain_random() {
+        fn naive(haystack1: &[u8], rest: &[Vec<u8>], needle: &[u8]) -> Option<usize> {
+            let haystack = rest.iter().fold(haystack1.to_vec(), |mut haystack, bytes| {
+                haystack.extend_from_slice(bytes);
+                haystack
+            });
+            (0..haystack1.len()).find(|&i| haystack[i..].starts_with(needle))
+        }
+
+        fn random_bytes(max_len: usize) -> Vec<u8> {
+            let len = fastrand::usize(..=max_len);
+            (0..len).map(|_| fastrand::u8(b'a'..=b'c')).collect()
+        }
+
+        for _ in 0..10_000 {
+            let haystack1 = random_bytes(16);
+            let rest = (0..fastrand::usize(..4))
+                .map(|_| random_bytes(4))
+                .collect::<Vec<_>>();
+            let needle = random_bytes(6);
+
+            assert_eq!(
+                find_bytes_chain(&haystack1, rest.iter().map(Vec::as_slice), &needle),
+                naive(&haystack1, &rest, &needle),
+                "haystack1={:?} rest={:?} needle={:?}",
+                haystack1,
+                rest,
+                needle
+            );
+        }
+    }
+
+    #[test]
+    fn search_bytes_ignore_ascii_case_random() {
+        fn random_bytes(max_len: usize) -> Vec<u8> {
+            let len = fastrand::usize(..=max_len);
+            let alphabet = b"abAB-";
+            (0..len)
+                .map(|_| alphabet[fastrand::usize(..alphabet.len())])
+                .collect()
+        }
+
+        for _ in 0..10_000 {
+            let haystack1 = random_bytes(16);
+            let rest = (0..fastrand::usize(..4))
+                .map(|_| random_bytes(4))
+                .collect::<Vec<_>>();
+            let needle = random_bytes(6);
+
+            let lowercase = |bytes: &[u8]| bytes.to_ascii_lowercase();
+            let rest_lowercase = rest
+                .iter()
+                .map(|bytes| lowercase(bytes))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                find_bytes_chain_ignore_ascii_case(
+                    &haystack1,
+                    rest.iter().map(Vec::as_slice),
+                    &needle
+                ),
+                find_bytes_chain(
+                    &lowercase(&haystack1),
+                    rest_lowercase.iter().map(Vec::as_slice),
+                    &lowercase(&needle)
+                ),
+                "haystack1={:?} rest={:?} needle={:?}",
+                haystack1,
+                rest,
+                needle
+            );
+            assert_eq!(
+                starts_with_chain_ignore_ascii_case(
+                    iter::once(&haystack1[..]).chain(rest.iter().map(Vec::as_slice)),
+                    &needle
+                ),
+                starts_with_chain(
+                    iter::once(&lowercase(&haystack1)[..])
+                        .chain(rest_lowercase.iter().map(Vec::as_slice)),
+                    &lowercase(&needle)
+                ),
+            );
+        }
+    }
+
+    #[test]
+    fn search_bytes_split() {
+        assert_eq!(find_bytes_split(b"abcd", b"efgh", b"abc"), Some(0));
+        assert_eq!(find_bytes_split(b"abcd", b"efgh", b"defg"), Some(3));
+        assert_eq!(find_bytes_split(b"abcd", b"efgh", b"efg"), None);
+        assert_eq!(find_bytes_split(b"abcd", b"", b"cde"), None);
+    }
+
+    #[test]
+    fn starts_with_between_() {
+        assert!(starts_with_between(b"ab", b"cd", b"abc"));
+        assert!(starts_with_between(b"abcd", b"", b"ab"));
+        assert!(starts_with_between(b"", b"abcd", b"ab"));
+        assert!(!starts_with_between(b"ab", b"", b"abc"));
+        assert!(!starts_with_between(b"ab", b"dc", b"abc"));
+    }
+
+    #[test]
+    fn starts_with_chain_() {
+        let haystacks: &[&[u8]] = &[b"a", b"bc", b"", b"d"];
+        assert!(starts_with_chain(haystacks.iter().copied(), b"abcd"));
+        assert!(starts_with_chain(haystacks.iter().copied(), b"ab"));
+        assert!(starts_with_chain(haystacks.iter().copied(), b""));
+        assert!(!starts_with_chain(haystacks.iter().copied(), b"abcde"));
+        assert!(!starts_with_chain(haystacks.iter().copied(), b"abd"));
+    }
+}
@@ -0,0 +1,140 @@
+//! [axum] extractor for `multipart/form-data` request bodies.
+//!
+//! Targets axum 0.8.
+//!
+//! ```rust
+//! use futures_util::stream::TryStreamExt;
+//! use multiparty::server::axum::Multipart;
+//!
+//! async fn handler(mut multipart: Multipart) -> Result<String, std::io::Error> {
+//!     let mut names = Vec::new();
+//!     while let Some(part) = multipart.try_next().await? {
+//!         if let Ok(headers) = part.raw_headers().parse() {
+//!             names.push(headers.name);
+//!         }
+//!     }
+//!
+//!     Ok(names.join(", "))
+//! }
+//! ```
+//!
+//! The body is limited by axum's `DefaultBodyLimit`, like axum's own `Multipart`.
+//! The decoder is configured through the [`FormDataBuilder`] found in the extensions
+//! of the request, if any, which can be added through axum's `Extension` layer.
+//!
+//! ```rust
+//! use multiparty::server::owned_futures03::FormDataBuilder;
+//! # let mut request = axum_core::extract::Request::new(axum_core::body::Body::empty());
+//!
+//! // What `axum::Extension(builder)` does when used as a layer
+//! request
+//!     .extensions_mut()
+//!     .insert(FormDataBuilder::new().max_part_size(1024 * 1024).max_parts(16));
+//! ```
+//!
+//! [axum]: https://docs.rs/axum
+
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use axum_core::body::BodyDataStream;
+use axum_core::extract::{FromRequest, Request};
+use axum_core::response::{IntoResponse, Response};
+use axum_core::RequestExt;
+use bytes::Bytes;
+use futures_core::Stream;
+use http::{header, StatusCode};
+
+use super::owned_futures03::{FormData, FormDataBuilder};
+use crate::boundary::from_content_type;
+
+/// A `multipart/form-data` request body.
+///
+/// Implements axum's `FromRequest`, taking the boundary
+/// from the `Content-Type` header of the request.
+/// See the [module level documentation](self) for how to configure it.
+pub type Multipart = FormData<BodyStream>;
+
+/// The body of a request, as a `Stream` of [`Bytes`].
+pub struct BodyStream {
+    inner: BodyDataStream,
+}
+
+/// Rejection returned when [`Multipart`] can't be extracted from a request.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum MultipartRejection {
+    /// The request doesn't have a `Content-Type` header.
+    MissingContentType,
+    /// The `Content-Type` of the request isn't `multipart/*` with a `boundary`.
+    InvalidContentType,
+}
+
+impl<S> FromRequest<S> for FormData<BodyStream>
+where
+    S: Send + Sync,
+{
+    type Rejection = MultipartRejection;
+
+    async fn from_request(req: Request, _state: &S) -> Result<Self, Self::Rejection> {
+        let (parts, body) = req.with_limited_body().into_parts();
+
+        let content_type = parts
+            .headers
+            .get(header::CONTENT_TYPE)
+            .ok_or(MultipartRejection::MissingContentType)?;
+        let content_type = content_type
+            .to_str()
+            .map_err(|_| MultipartRejection::InvalidContentType)?;
+        let boundary =
+            from_content_type(content_type).ok_or(MultipartRejection::InvalidContentType)?;
+
+        let stream = BodyStream {
+            inner: body.into_data_stream(),
+        };
+        let form_data = match parts.extensions.get::<FormDataBuilder>() {
+            Some(builder) => builder.build(stream, boundary),
+            None => FormData::new(stream, boundary),
+        };
+        Ok(form_data)
+    }
+}
+
+impl Stream for BodyStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match Pin::new(&mut self.inner).poll_next(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some(Ok(bytes))) => Poll::Ready(Some(Ok(bytes))),
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(io::Error::other(err)))),
+            Poll::Ready(None) => Poll::Ready(None),
+        }
+    }
+}
+
+impl Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BodyStream").finish()
+    }
+}
+
+impl Display for MultipartRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingContentType => f.write_str("missing Content-Type header"),
+            Self::InvalidContentType => f.write_str("Content-Type isn't multipart with a boundary"),
+        }
+    }
+}
+
+impl StdError for MultipartRejection {}
+
+impl IntoResponse for MultipartRejection {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, self.to_string()).into_response()
+    }
+}
@@ -0,0 +1,159 @@
+//! Blocking [`std::io::Read`] multipart decoder.
+//!
+//! ```rust
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use std::io::Read;
+//!
+//! use multiparty::server::blocking::FormData;
+//!
+//! # if false {
+//! let boundary = todo!("A multipart/form-data boundary");
+//! let reader = todo!("A std::io::Read");
+//! # }
+//! # let boundary = "abcd";
+//! # let reader = &b"--abcd\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n--abcd--"[..];
+//! let mut multipart = FormData::new(reader, boundary);
+//!
+//! while let Some(mut part) = multipart.next_part()? {
+//!     let headers = part.raw_headers().parse()?;
+//!     println!("name: {:?}", headers.name);
+//! #   assert_eq!(headers.name, "foo");
+//!
+//!     let mut body = Vec::new();
+//!     part.read_to_end(&mut body)?;
+//!     println!("Read {} bytes from the current part", body.len());
+//! #   assert_eq!(body, b"bar");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{self, Debug};
+use std::io::{self, ErrorKind, Read as IoRead};
+use std::task::Poll;
+
+use super::reader::{Driver, PartBody};
+use super::sans_io::{self, Read};
+use crate::headers::RawHeaders;
+
+/// A blocking multipart/form-data decoder.
+///
+/// Yields [`Part`]s through [`FormData::next_part`].
+pub struct FormData<R> {
+    reader: R,
+    driver: Driver,
+}
+
+/// A single "part" of a `multipart/form-data` body.
+///
+/// Yielded by [`FormData::next_part`]. The body of the part
+/// can be read through the [`std::io::Read`] implementation.
+pub struct Part<'a, R> {
+    headers: RawHeaders,
+    body: PartBody,
+
+    form_data: &'a mut FormData<R>,
+}
+
+impl<R> FormData<R> {
+    /// Construct a new `FormData` from a [`std::io::Read`] and a `boundary`.
+    pub fn new(reader: R, boundary: &str) -> Self {
+        Self::from_sans_io(reader, sans_io::FormData::new(boundary))
+    }
+
+    /// Construct a new `FormData` from an already configured [`sans_io::FormData`].
+    ///
+    /// ```rust
+    /// use multiparty::server::sans_io;
+    /// use multiparty::server::blocking::FormData;
+    ///
+    /// let inner = sans_io::FormData::builder()
+    ///     .max_part_size(1024 * 1024)
+    ///     .max_parts(16)
+    ///     .build("abcd");
+    /// let multipart = FormData::from_sans_io(&b""[..], inner);
+    /// # drop(multipart);
+    /// ```
+    pub fn from_sans_io(reader: R, inner: sans_io::FormData) -> Self {
+        Self {
+            reader,
+            driver: Driver::new(inner),
+        }
+    }
+}
+
+impl<R> FormData<R>
+where
+    R: IoRead,
+{
+    /// Read the next [`Part`] in this multipart stream.
+    ///
+    /// Whatever is left of the body of the previous [`Part`] is skipped.
+    /// Returns `Ok(None)` once the end of the multipart stream has been reached.
+    pub fn next_part(&mut self) -> io::Result<Option<Part<'_, R>>> {
+        loop {
+            match self.read()? {
+                Read::NewPart { headers, .. } => {
+                    return Ok(Some(Part {
+                        headers,
+                        body: PartBody::new(),
+                        form_data: self,
+                    }))
+                }
+                Read::Part(_) | Read::PartEof => {
+                    // Skip the body of the previous part
+                }
+                Read::Eof => return Ok(None),
+                Read::NeedsWrite | Read::None => unreachable!(),
+            }
+        }
+    }
+
+    /// Drive the decoder until it yields something other than
+    /// [`Read::NeedsWrite`] or [`Read::None`].
+    fn read(&mut self) -> io::Result<Read> {
+        let reader = &mut self.reader;
+        let poll = self.driver.poll_read(|buf| loop {
+            match reader.read(buf) {
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                result => return Poll::Ready(result),
+            }
+        });
+        match poll {
+            Poll::Ready(result) => result,
+            Poll::Pending => unreachable!("blocking reads are always ready"),
+        }
+    }
+}
+
+impl<R> Debug for FormData<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FormData").finish()
+    }
+}
+
+impl<'a, R> Part<'a, R> {
+    /// Access the raw headers of this [`Part`].
+    pub fn raw_headers(&self) -> &RawHeaders {
+        &self.headers
+    }
+}
+
+impl<'a, R> IoRead for Part<'a, R>
+where
+    R: IoRead,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let form_data = &mut self.form_data;
+        match self.body.poll_read(buf, || Poll::Ready(form_data.read())) {
+            Poll::Ready(result) => result,
+            Poll::Pending => unreachable!("blocking reads are always ready"),
+        }
+    }
+}
+
+impl<'a, R> Debug for Part<'a, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Part").finish()
+    }
+}
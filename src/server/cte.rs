@@ -0,0 +1,139 @@
+//! `Content-Transfer-Encoding` decoders.
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// The value of the `=` padding character
+const PAD: u8 = 64;
+
+/// Streaming base64 decoder
+///
+/// Whitespace, like the line breaks of MIME encoded bodies, is ignored.
+/// Incomplete groups of 4 characters are kept until the next call to
+/// [`Base64Decoder::decode`], so input can be split at any point.
+#[derive(Debug, Default)]
+pub(super) struct Base64Decoder {
+    group: [u8; 4],
+    len: usize,
+    padded: bool,
+}
+
+impl Base64Decoder {
+    /// Decode `bytes`, returning `None` if they aren't valid base64.
+    pub(super) fn decode(&mut self, bytes: &[u8]) -> Option<Bytes> {
+        let mut decoded = BytesMut::with_capacity((self.len + bytes.len()) / 4 * 3);
+
+        for &b in bytes {
+            let value = match b {
+                b'A'..=b'Z' => b - b'A',
+                b'a'..=b'z' => b - b'a' + 26,
+                b'0'..=b'9' => b - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                b'=' => PAD,
+                b'\r' | b'\n' | b' ' | b'\t' => continue,
+                _ => return None,
+            };
+
+            if value == PAD {
+                // Padding can only take the place of the last 2 characters
+                if self.len < 2 {
+                    return None;
+                }
+                self.padded = true;
+            } else if self.padded {
+                // Data after the padding
+                return None;
+            }
+
+            self.group[self.len] = value;
+            self.len += 1;
+
+            if self.len == 4 {
+                self.flush(&mut decoded);
+            }
+        }
+
+        Some(decoded.freeze())
+    }
+
+    /// Decode the last group, which may be missing its padding.
+    ///
+    /// Returns `None` if the last group is incomplete.
+    pub(super) fn finish(&mut self) -> Option<Bytes> {
+        match self.len {
+            0 => Some(Bytes::new()),
+            1 => None,
+            _ => {
+                for value in &mut self.group[self.len..] {
+                    *value = PAD;
+                }
+
+                let mut decoded = BytesMut::with_capacity(2);
+                self.flush(&mut decoded);
+                Some(decoded.freeze())
+            }
+        }
+    }
+
+    fn flush(&mut self, decoded: &mut BytesMut) {
+        let len = match self.group {
+            [_, _, PAD, _] => 1,
+            [_, _, _, PAD] => 2,
+            _ => 3,
+        };
+
+        let n = self
+            .group
+            .iter()
+            .map(|&value| if value == PAD { 0 } else { u32::from(value) })
+            .fold(0, |n, value| (n << 6) | value);
+        decoded.put_slice(&n.to_be_bytes()[1..=len]);
+
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(encoded: &[u8], chunk_size: usize) -> Option<Vec<u8>> {
+        let mut decoder = Base64Decoder::default();
+        let mut decoded = Vec::new();
+        for chunk in encoded.chunks(chunk_size) {
+            decoded.extend_from_slice(&decoder.decode(chunk)?);
+        }
+        decoded.extend_from_slice(&decoder.finish()?);
+        Some(decoded)
+    }
+
+    #[test]
+    fn base64() {
+        let cases: &[(&[u8], &[u8])] = &[
+            (b"", b""),
+            (b"Zg==", b"f"),
+            (b"Zm8=", b"fo"),
+            (b"Zm9v", b"foo"),
+            (b"Zm9vYg==", b"foob"),
+            (b"Zm9vYmE=", b"fooba"),
+            (b"Zm9vYmFy", b"foobar"),
+            (b"Zm9v\r\nYmFy\r\n", b"foobar"),
+            (b"Zm9vYg", b"foob"),
+            (b"+/+/", &[0xfb, 0xff, 0xbf]),
+        ];
+
+        for &(encoded, expected) in cases {
+            for chunk_size in 1..=encoded.len().max(1) {
+                assert_eq!(decode(encoded, chunk_size).as_deref(), Some(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(decode(b"Zm9v!", 4).is_none());
+        assert!(decode(b"Z===", 4).is_none());
+        assert!(decode(b"Zg==Zg==", 4).is_none());
+        assert!(decode(b"Zm9vY", 4).is_none());
+    }
+}
@@ -0,0 +1,183 @@
+//! [`futures-io`] [`AsyncRead`] multipart decoder.
+//!
+//! Works with any runtime using the `futures-io` traits, like `async-std` and `smol`.
+//! Runtime agnostic `Stream`s of [`Bytes`] can be decoded through [`owned_futures03`] instead.
+//!
+//! NOTE: Currently requires the reader to also be [`Unpin`].
+//!
+//! ```rust
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use futures_util::io::AsyncReadExt;
+//! use multiparty::server::futures_io::FormData;
+//!
+//! # if false {
+//! let boundary = todo!("A multipart/form-data boundary");
+//! let reader = todo!("A futures_io::AsyncRead + Unpin");
+//! # }
+//! # let boundary = "abcd";
+//! # let reader = &b"--abcd\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n--abcd--"[..];
+//! let mut multipart = FormData::new(reader, boundary);
+//!
+//! while let Some(mut part) = multipart.next_part().await? {
+//!     let headers = part.raw_headers().parse()?;
+//!     println!("name: {:?}", headers.name);
+//! #   assert_eq!(headers.name, "foo");
+//!
+//!     let mut body = Vec::new();
+//!     part.read_to_end(&mut body).await?;
+//!     println!("Read {} bytes from the current part", body.len());
+//! #   assert_eq!(body, b"bar");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`futures-io`]: https://docs.rs/futures-io
+//! [`AsyncRead`]: ::futures_io::AsyncRead
+//! [`Bytes`]: bytes::Bytes
+//! [`owned_futures03`]: crate::server::owned_futures03
+
+use std::fmt::{self, Debug};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use super::reader::{Driver, PartBody};
+use super::sans_io::{self, Read};
+use crate::headers::RawHeaders;
+use ::futures_io::AsyncRead;
+
+/// A multipart/form-data decoder reading from an [`AsyncRead`].
+///
+/// Yields [`Part`]s through [`FormData::next_part`].
+pub struct FormData<R> {
+    reader: R,
+    driver: Driver,
+}
+
+/// A single "part" of a `multipart/form-data` body.
+///
+/// Yielded by [`FormData::next_part`]. The body of the part
+/// can be read through the [`AsyncRead`] implementation.
+pub struct Part<'a, R> {
+    headers: RawHeaders,
+    body: PartBody,
+
+    form_data: &'a mut FormData<R>,
+}
+
+impl<R> FormData<R> {
+    /// Construct a new `FormData` from an [`AsyncRead`] and a `boundary`.
+    pub fn new(reader: R, boundary: &str) -> Self {
+        Self::from_sans_io(reader, sans_io::FormData::new(boundary))
+    }
+
+    /// Construct a new `FormData` from an already configured [`sans_io::FormData`].
+    ///
+    /// ```rust
+    /// use multiparty::server::sans_io;
+    /// use multiparty::server::futures_io::FormData;
+    ///
+    /// let inner = sans_io::FormData::builder()
+    ///     .max_part_size(1024 * 1024)
+    ///     .max_parts(16)
+    ///     .build("abcd");
+    /// let multipart = FormData::from_sans_io(&b""[..], inner);
+    /// # drop(multipart);
+    /// ```
+    pub fn from_sans_io(reader: R, inner: sans_io::FormData) -> Self {
+        Self {
+            reader,
+            driver: Driver::new(inner),
+        }
+    }
+}
+
+impl<R> FormData<R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Read the next [`Part`] in this multipart stream.
+    ///
+    /// Whatever is left of the body of the previous [`Part`] is skipped.
+    /// Returns `Ok(None)` once the end of the multipart stream has been reached.
+    pub async fn next_part(&mut self) -> io::Result<Option<Part<'_, R>>> {
+        loop {
+            match (ReadFuture { form_data: self }).await? {
+                Read::NewPart { headers, .. } => {
+                    return Ok(Some(Part {
+                        headers,
+                        body: PartBody::new(),
+                        form_data: self,
+                    }))
+                }
+                Read::Part(_) | Read::PartEof => {
+                    // Skip the body of the previous part
+                }
+                Read::Eof => return Ok(None),
+                Read::NeedsWrite | Read::None => unreachable!(),
+            }
+        }
+    }
+
+    /// Drive the decoder until it yields something other than
+    /// [`Read::NeedsWrite`] or [`Read::None`].
+    fn poll_read(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Read>> {
+        let reader = &mut self.reader;
+        self.driver
+            .poll_read(|buf| Pin::new(&mut *reader).poll_read(cx, buf))
+    }
+}
+
+impl<R> Debug for FormData<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FormData").finish()
+    }
+}
+
+/// Future resolving to the next [`Read`] yielded by [`FormData::poll_read`]
+struct ReadFuture<'a, R> {
+    form_data: &'a mut FormData<R>,
+}
+
+impl<'a, R> Future for ReadFuture<'a, R>
+where
+    R: AsyncRead + Unpin,
+{
+    type Output = io::Result<Read>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.form_data.poll_read(cx)
+    }
+}
+
+impl<'a, R> Part<'a, R> {
+    /// Access the raw headers of this [`Part`].
+    pub fn raw_headers(&self) -> &RawHeaders {
+        &self.headers
+    }
+}
+
+impl<'a, R> AsyncRead for Part<'a, R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let Part {
+            body, form_data, ..
+        } = &mut *self;
+        body.poll_read(buf, || form_data.poll_read(cx))
+    }
+}
+
+impl<'a, R> Debug for Part<'a, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Part").finish()
+    }
+}
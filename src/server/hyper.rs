@@ -0,0 +1,121 @@
+//! [hyper] adapter for `multipart/form-data` request bodies.
+//!
+//! Targets hyper 1.0, but works with any [`http_body::Body`], like hyper's `Incoming`.
+//!
+//! ```rust
+//! use futures_util::stream::TryStreamExt;
+//! use multiparty::server::hyper::from_body;
+//!
+//! async fn handler<B>(body: B, boundary: &str) -> std::io::Result<Vec<String>>
+//! where
+//!     B: http_body::Body<Data = bytes::Bytes>,
+//!     B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+//! {
+//!     let mut multipart = from_body(body, boundary);
+//!
+//!     let mut names = Vec::new();
+//!     while let Some(part) = multipart.try_next().await? {
+//!         if let Ok(headers) = part.raw_headers().parse() {
+//!             names.push(headers.name);
+//!         }
+//!     }
+//!     Ok(names)
+//! }
+//! ```
+//!
+//! [hyper]: https://docs.rs/hyper
+
+use std::error::Error as StdError;
+use std::fmt::{self, Debug};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use http_body::Body;
+use pin_project_lite::pin_project;
+
+use super::owned_futures03::FormData;
+
+/// A `multipart/form-data` request body.
+///
+/// Returned by [`from_body`].
+pub type Multipart<B> = FormData<BodyStream<B>>;
+
+pin_project! {
+    /// The data frames of an [`http_body::Body`], as a `Stream` of [`Bytes`].
+    ///
+    /// Trailers are ignored.
+    pub struct BodyStream<B> {
+        #[pin]
+        body: B,
+    }
+}
+
+/// Decode `body` as a `multipart/form-data` stream separated by `boundary`.
+///
+/// Use [`boundary::from_content_type`](crate::boundary::from_content_type)
+/// to get the `boundary` from the `Content-Type` header of the request.
+pub fn from_body<B>(body: B, boundary: &str) -> Multipart<B>
+where
+    B: Body<Data = Bytes>,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    FormData::new(BodyStream::new(body), boundary)
+}
+
+impl<B> BodyStream<B> {
+    /// Wrap `body` into a `Stream` of [`Bytes`].
+    pub fn new(body: B) -> Self {
+        Self { body }
+    }
+
+    /// Get back the wrapped body.
+    pub fn into_inner(self) -> B {
+        self.body
+    }
+}
+
+impl<B> Stream for BodyStream<B>
+where
+    B: Body<Data = Bytes>,
+    B::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut body = self.project().body;
+
+        loop {
+            match body.as_mut().poll_frame(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(frame))) => {
+                    if let Ok(bytes) = frame.into_data() {
+                        return Poll::Ready(Some(Ok(bytes)));
+                    }
+
+                    // Skip the trailers
+                }
+                Poll::Ready(Some(Err(err))) => {
+                    return Poll::Ready(Some(Err(io::Error::other(err))))
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.body.is_end_stream() {
+            (0, Some(0))
+        } else {
+            (0, None)
+        }
+    }
+}
+
+impl<B> Debug for BodyStream<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BodyStream").finish()
+    }
+}
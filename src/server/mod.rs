@@ -1,9 +1,34 @@
 //! Multipart decoder implementations
 
+#[cfg(feature = "axum")]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+pub mod axum;
+#[cfg(feature = "blocking")]
+#[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+pub mod blocking;
+#[cfg(feature = "cte")]
+mod cte;
+#[cfg(feature = "futures-io")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures-io")))]
+pub mod futures_io;
+#[cfg(feature = "hyper")]
+#[cfg_attr(docsrs, doc(cfg(feature = "hyper")))]
+pub mod hyper;
 #[cfg(feature = "futures03")]
 #[cfg_attr(docsrs, doc(cfg(feature = "futures03")))]
 pub mod owned_futures03;
 #[cfg(feature = "futures03")]
 #[cfg_attr(docsrs, doc(cfg(feature = "futures03")))]
-pub(super) mod plain_futures03;
+pub mod plain_futures03;
+#[cfg(any(feature = "blocking", feature = "tokio", feature = "futures-io"))]
+mod reader;
 pub mod sans_io;
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+pub mod tokio;
+#[cfg(feature = "tokio-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-util")))]
+pub mod tokio_util;
+#[cfg(feature = "warp")]
+#[cfg_attr(docsrs, doc(cfg(feature = "warp")))]
+pub mod warp;
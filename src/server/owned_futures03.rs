@@ -1,26 +1,79 @@
 //! `futures` `Stream` 0.3 high-level multipart decoder.
-//!
-//! NOTE: Currently requires the stream to also be [`Unpin`].
 
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt::{self, Debug};
+use std::future::Future;
 use std::io::{Error, ErrorKind, Result};
 use std::mem;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
-use bytes::Bytes;
+use bytes::{BufMut, Bytes, BytesMut};
 use futures_core::stream::{FusedStream, Stream};
 use try_lock::TryLock;
 
 use super::plain_futures03::{self, Read};
-use crate::headers::RawHeaders;
+use super::sans_io;
+use crate::boundary::Boundary;
+use crate::headers::{self, Headers, RawHeaders};
+use crate::utils::join_bytes;
 
 /// A `Stream` of multipart/form-data parts.
 ///
 /// Yields [`Part`].
+///
+/// # Errors
+///
+/// Errors of the underlying `Stream` are yielded as is, while decoding errors
+/// are yielded as an [`io::Error`](std::io::Error) wrapping a [`sans_io::Error`],
+/// which can be matched on through [`sans_io::Error::from_io_error`].
+///
+/// # Cancellation
+///
+/// The decoder is only ever modified within a single poll, so futures polling
+/// `FormData` or [`Part`] can be dropped at any time, like when they're wrapped
+/// in a timeout to stop slow uploads, without leaving the decoder in a broken state.
+/// A cancelled [`Part::read_to_end`] keeps what it read, so it can be retried.
 pub struct FormData<S> {
-    inner: Arc<TryLock<Option<plain_futures03::FormData<S>>>>,
+    inner: Inner<S>,
+    /// Options set while a [`Part`] was holding the lock, applied once it's released
+    pending: Vec<Configure>,
+    boundary: Boundary,
+    terminated: Arc<AtomicBool>,
+}
+
+/// A builder for configuring [`FormData`].
+///
+/// Returned by [`FormData::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct FormDataBuilder {
+    inner: sans_io::FormDataBuilder,
+}
+
+/// A `Stream` of parts along with their parsed [`Headers`].
+///
+/// Returned by [`FormData::parts`].
+pub struct Parts<S> {
+    form_data: FormData<S>,
+}
+
+/// A `Stream` of parts with their parsed [`Headers`] and their whole body.
+///
+/// Returned by [`FormData::buffered_parts`].
+pub struct BufferedParts<S> {
+    parts: Parts<S>,
+    current: Option<BufferedPart<S>>,
+}
+
+/// The part being read by [`BufferedParts`]
+struct BufferedPart<S> {
+    headers: Headers,
+    part: Part<S>,
+    first: Bytes,
+    body: BytesMut,
 }
 
 /// A single "part" of a `multipart/form-data` body.
@@ -28,23 +81,539 @@ pub struct FormData<S> {
 /// Yielded by the [`FormData`] `Stream`.
 pub struct Part<S> {
     headers: RawHeaders,
+    parsed: std::result::Result<Headers, headers::Error>,
+    index: usize,
+    /// See [`sans_io::FormData::content_length_hint`]
+    capacity_hint: usize,
+    /// The `Content-Length` of this `Part`, if it's being validated
+    content_length: Option<u64>,
+    bytes_read: u64,
+    /// What a cancelled [`Part::read_to_end`] had already read, see [`push_bytes`]
+    read_first: Bytes,
+    read_body: BytesMut,
 
-    inner: Option<Arc<TryLock<Option<plain_futures03::FormData<S>>>>>,
+    inner: Option<Inner<S>>,
+    terminated: Arc<AtomicBool>,
+}
+
+/// The decoder shared between [`FormData`] and the last [`Part`].
+///
+/// Boxed, so that the stream doesn't have to be [`Unpin`].
+type Inner<S> = Arc<TryLock<Option<Pin<Box<plain_futures03::FormData<S>>>>>>;
+
+type Configure = Box<dyn FnOnce(&mut sans_io::FormData) + Send + Sync>;
+
+impl FormData<()> {
+    /// Create a [`FormDataBuilder`], for configuring multiple options at once.
+    ///
+    /// ```
+    /// use multiparty::server::owned_futures03::FormData;
+    /// # let stream = futures_util::stream::empty::<std::io::Result<bytes::Bytes>>();
+    ///
+    /// let form_data = FormData::builder()
+    ///     .max_part_size(1024 * 1024)
+    ///     .max_parts(16)
+    ///     .build(stream, "abcdef");
+    /// ```
+    pub fn builder() -> FormDataBuilder {
+        FormDataBuilder::new()
+    }
 }
 
 impl<S> FormData<S> {
-    /// Construct a new `FormData` from a `Stream<Item = std::io::Result<Bytes>> + Unpin` and a `boundary`.
+    /// Construct a new `FormData` from a `Stream<Item = std::io::Result<Bytes>>` and a `boundary`.
     pub fn new(stream: S, boundary: &str) -> Self {
         let inner_form = plain_futures03::FormData::new(stream, boundary);
+        Self::from_inner(inner_form)
+    }
+
+    /// Construct a new `FormData` which accepts at most `max_headers` headers in each part.
+    ///
+    /// See [`sans_io::FormData::with_max_headers`] for more details.
+    ///
+    /// [`sans_io::FormData::with_max_headers`]: crate::server::sans_io::FormData::with_max_headers
+    pub fn with_max_headers(stream: S, boundary: &str, max_headers: usize) -> Self {
+        let inner_form = plain_futures03::FormData::with_max_headers(stream, boundary, max_headers);
+        Self::from_inner(inner_form)
+    }
+
+    /// Turn this `FormData` into a `Stream` yielding each [`Part`] along with its parsed [`Headers`].
+    ///
+    /// Errors encountered while parsing the headers are yielded as `Stream` errors.
+    pub fn parts(self) -> Parts<S> {
+        Parts { form_data: self }
+    }
+
+    /// Turn this `FormData` into a `Stream` yielding each part along with its parsed [`Headers`]
+    /// and its whole body, read into memory.
+    ///
+    /// Parts with a body bigger than `max_part_size` bytes make the `Stream` yield an error.
+    /// This overrides the limit set by [`FormData::set_max_part_size`], but not the more
+    /// specific ones set by [`FormData::set_max_field_size`] and [`FormData::set_max_file_size`].
+    pub fn buffered_parts(mut self, max_part_size: usize) -> BufferedParts<S> {
+        self.set_max_part_size(max_part_size);
+        BufferedParts {
+            parts: self.parts(),
+            current: None,
+        }
+    }
+
+    fn from_inner(inner_form: plain_futures03::FormData<S>) -> Self {
         Self {
-            inner: Arc::new(TryLock::new(Some(inner_form))),
+            boundary: Boundary::new(inner_form.sans_io().boundary()),
+            inner: Arc::new(TryLock::new(Some(Box::pin(inner_form)))),
+            pending: Vec::new(),
+            terminated: Arc::new(AtomicBool::new(false)),
         }
     }
+
+    /// Limit the size of the body of each [`Part`] to `max_part_size` bytes.
+    ///
+    /// Polling a [`Part`] with a bigger body yields an error.
+    /// See [`sans_io::FormData::set_max_part_size`] for more details.
+    pub fn set_max_part_size(&mut self, max_part_size: usize) {
+        self.configure(move |inner| inner.set_max_part_size(max_part_size));
+    }
+
+    /// Limit the size of the body of each [`Part`] without a `filename` to `max_field_size` bytes.
+    ///
+    /// See [`sans_io::FormData::set_max_field_size`] for more details.
+    pub fn set_max_field_size(&mut self, max_field_size: usize) {
+        self.configure(move |inner| inner.set_max_field_size(max_field_size));
+    }
+
+    /// Limit the size of the body of each [`Part`] with a `filename` to `max_file_size` bytes.
+    ///
+    /// See [`sans_io::FormData::set_max_file_size`] for more details.
+    pub fn set_max_file_size(&mut self, max_file_size: usize) {
+        self.configure(move |inner| inner.set_max_file_size(max_file_size));
+    }
+
+    /// Limit the size of the whole multipart stream to `max_total_size` bytes.
+    ///
+    /// Polling past the limit yields an error.
+    /// See [`sans_io::FormData::set_max_total_size`] for more details.
+    pub fn set_max_total_size(&mut self, max_total_size: usize) {
+        self.configure(move |inner| inner.set_max_total_size(max_total_size));
+    }
+
+    /// Limit the number of parts in the multipart stream to `max_parts`.
+    ///
+    /// Polling more parts than the limit yields an error.
+    /// See [`sans_io::FormData::set_max_parts`] for more details.
+    pub fn set_max_parts(&mut self, max_parts: usize) {
+        self.configure(move |inner| inner.set_max_parts(max_parts));
+    }
+
+    /// Limit the size of the headers of each [`Part`] to `max_header_size` bytes.
+    ///
+    /// Polling a [`Part`] with bigger headers out of this `FormData` yields an error.
+    /// See [`sans_io::FormData::set_max_header_size`] for more details.
+    pub fn set_max_header_size(&mut self, max_header_size: usize) {
+        self.configure(move |inner| inner.set_max_header_size(max_header_size));
+    }
+
+    /// Limit the length of the name and of the value of each header of each [`Part`].
+    ///
+    /// See [`sans_io::FormData::set_max_header_field_size`] for more details.
+    pub fn set_max_header_field_size(
+        &mut self,
+        max_header_name_size: usize,
+        max_header_value_size: usize,
+    ) {
+        self.configure(move |inner| {
+            inner.set_max_header_field_size(max_header_name_size, max_header_value_size)
+        });
+    }
+
+    /// Accept bare `\n` line endings in addition to `\r\n`.
+    ///
+    /// See [`sans_io::FormData::set_lenient`] for more details.
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.configure(move |inner| inner.set_lenient(lenient));
+    }
+
+    /// Match the boundary ignoring ASCII case.
+    ///
+    /// See [`sans_io::FormData::set_boundary_ignore_ascii_case`] for more details.
+    pub fn set_boundary_ignore_ascii_case(&mut self, boundary_ignore_ascii_case: bool) {
+        self.configure(move |inner| {
+            inner.set_boundary_ignore_ascii_case(boundary_ignore_ascii_case)
+        });
+    }
+
+    /// Unfold header values continuing on the next line.
+    ///
+    /// See [`sans_io::FormData::set_unfold_headers`] for more details.
+    pub fn set_unfold_headers(&mut self, unfold_headers: bool) {
+        self.configure(move |inner| inner.set_unfold_headers(unfold_headers));
+    }
+
+    /// Strip the UTF-8 byte order mark some clients put at the start of text fields.
+    ///
+    /// See [`sans_io::FormData::set_strip_bom`] for more details.
+    pub fn set_strip_bom(&mut self, strip_bom: bool) {
+        self.configure(move |inner| inner.set_strip_bom(strip_bom));
+    }
+
+    /// Accept and discard bytes after the closing boundary.
+    ///
+    /// See [`sans_io::FormData::set_discard_epilogue`] for more details.
+    pub fn set_discard_epilogue(&mut self, discard_epilogue: bool) {
+        self.configure(move |inner| inner.set_discard_epilogue(discard_epilogue));
+    }
+
+    /// Reject multipart streams which end without the closing boundary.
+    ///
+    /// See [`sans_io::FormData::set_require_closing_boundary`] for more details.
+    pub fn set_require_closing_boundary(&mut self, require_closing_boundary: bool) {
+        self.configure(move |inner| inner.set_require_closing_boundary(require_closing_boundary));
+    }
+
+    /// Treat boundaries followed by an unexpected suffix as part of the body.
+    ///
+    /// See [`sans_io::FormData::set_allow_false_boundaries`] for more details.
+    pub fn set_allow_false_boundaries(&mut self, allow_false_boundaries: bool) {
+        self.configure(move |inner| inner.set_allow_false_boundaries(allow_false_boundaries));
+    }
+
+    /// Collect the headers of each [`Part`] into its [`RawHeaders`].
+    ///
+    /// See [`sans_io::FormData::set_collect_headers`] for more details.
+    pub fn set_collect_headers(&mut self, collect_headers: bool) {
+        self.configure(move |inner| inner.set_collect_headers(collect_headers));
+    }
+
+    /// Check that the body of each [`Part`] is as long as its `Content-Length` header declares.
+    ///
+    /// See [`sans_io::FormData::set_validate_content_length`] for more details.
+    pub fn set_validate_content_length(&mut self, validate_content_length: bool) {
+        self.configure(move |inner| inner.set_validate_content_length(validate_content_length));
+    }
+
+    /// Decode the body of parts according to their `Content-Transfer-Encoding` header.
+    ///
+    /// See [`sans_io::FormData::set_decode_transfer_encoding`] for more details.
+    #[cfg(feature = "cte")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cte")))]
+    pub fn set_decode_transfer_encoding(&mut self, decode_transfer_encoding: bool) {
+        self.configure(move |inner| inner.set_decode_transfer_encoding(decode_transfer_encoding));
+    }
+
+    /// The boundary delimiting the parts, as it was passed to the constructor.
+    pub fn boundary(&self) -> &str {
+        self.boundary.as_str()
+    }
+
+    /// Call `on_event` every time a [`Part`] starts, yields some bytes or ends,
+    /// and once when the multipart stream ends.
+    ///
+    /// See [`sans_io::FormData::set_on_event`] for more details.
+    pub fn set_on_event<F>(&mut self, on_event: F)
+    where
+        F: FnMut(sans_io::Event<'_>) + Send + Sync + 'static,
+    {
+        self.configure(move |inner| inner.set_on_event(on_event));
+    }
+
+    /// Whether the first boundary of the multipart stream has been found.
+    ///
+    /// Should be called once this `FormData` has been exhausted.
+    /// See [`sans_io::FormData::saw_any_boundary`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a [`Part`] of this `FormData` is being polled at the same time.
+    pub fn saw_any_boundary(&self) -> bool {
+        self.with_sans_io(|inner| inner.saw_any_boundary())
+    }
+
+    /// Whether the multipart stream ended without the closing boundary.
+    ///
+    /// Should be called once this `FormData` has been exhausted.
+    /// See [`sans_io::FormData::was_truncated`] for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a [`Part`] of this `FormData` is being polled at the same time.
+    pub fn was_truncated(&self) -> bool {
+        self.with_sans_io(|inner| inner.was_truncated())
+    }
+
+    fn configure<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut sans_io::FormData) + Send + Sync + 'static,
+    {
+        match self.inner.try_lock() {
+            Some(mut inner) => {
+                let inner = inner.as_mut().expect("inner should never be None");
+                let sans_io = inner.as_mut().sans_io_mut();
+                apply_pending(&mut self.pending, sans_io);
+                f(sans_io);
+            }
+            None => {
+                // A `Part` is being polled, instead of waiting for it
+                // apply `f` the next time the lock is taken
+                self.pending.push(Box::new(f));
+            }
+        }
+    }
+
+    fn with_sans_io<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut sans_io::FormData) -> T,
+    {
+        let mut inner = self
+            .inner
+            .try_lock()
+            .expect("a Part of this FormData is being polled at the same time");
+        let inner = inner.as_mut().expect("inner should never be None");
+        f(inner.as_mut().sans_io_mut())
+    }
+}
+
+fn apply_pending(pending: &mut Vec<Configure>, sans_io: &mut sans_io::FormData) {
+    for f in pending.drain(..) {
+        f(sans_io);
+    }
+}
+
+impl<S> FormData<S>
+where
+    S: Stream<Item = Result<Bytes>>,
+{
+    /// Read every field into memory, keyed by their name.
+    ///
+    /// Parts with a `filename` are skipped. When multiple fields have
+    /// the same name, the last one wins.
+    pub async fn into_fields(mut self) -> Result<HashMap<String, Bytes>> {
+        let mut fields = HashMap::new();
+
+        while let Some(mut part) = Next(&mut self).await.transpose()? {
+            let headers = part
+                .raw_headers()
+                .parse()
+                .map_err(|err| Error::new(ErrorKind::Other, err))?;
+            if headers.filename.is_some() {
+                // The body is skipped when polling the next part
+                continue;
+            }
+
+            let body = part.read_to_end().await?;
+            fields.insert(headers.name, body);
+        }
+
+        Ok(fields)
+    }
+
+    /// Skip to the first [`Part`] named `name`.
+    ///
+    /// Parts with a different name, or with headers which can't be parsed, are skipped.
+    /// Returns `None` if the stream ends before finding a [`Part`] named `name`.
+    pub async fn find_part(mut self, name: &str) -> Result<Option<Part<S>>> {
+        while let Some(part) = Next(&mut self).await.transpose()? {
+            if matches!(part.raw_headers().name(), Ok(part_name) if part_name == name) {
+                return Ok(Some(part));
+            }
+
+            // The body is skipped when polling the next part
+        }
+
+        Ok(None)
+    }
+}
+
+impl FormDataBuilder {
+    /// Create a new [`FormDataBuilder`] with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept at most `max_headers` headers in each [`Part`].
+    ///
+    /// See [`sans_io::FormDataBuilder::max_header_count`] for more details.
+    pub fn max_header_count(mut self, max_headers: usize) -> Self {
+        self.inner = self.inner.max_header_count(max_headers);
+        self
+    }
+
+    /// Limit the size of the body of each [`Part`] to `max_part_size` bytes.
+    ///
+    /// See [`sans_io::FormDataBuilder::max_part_size`] for more details.
+    pub fn max_part_size(mut self, max_part_size: usize) -> Self {
+        self.inner = self.inner.max_part_size(max_part_size);
+        self
+    }
+
+    /// Limit the size of the body of each [`Part`] without a `filename` to `max_field_size` bytes.
+    ///
+    /// See [`sans_io::FormDataBuilder::max_field_size`] for more details.
+    pub fn max_field_size(mut self, max_field_size: usize) -> Self {
+        self.inner = self.inner.max_field_size(max_field_size);
+        self
+    }
+
+    /// Limit the size of the body of each [`Part`] with a `filename` to `max_file_size` bytes.
+    ///
+    /// See [`sans_io::FormDataBuilder::max_file_size`] for more details.
+    pub fn max_file_size(mut self, max_file_size: usize) -> Self {
+        self.inner = self.inner.max_file_size(max_file_size);
+        self
+    }
+
+    /// Limit the size of the whole multipart stream to `max_total_size` bytes.
+    ///
+    /// See [`sans_io::FormDataBuilder::max_total_size`] for more details.
+    pub fn max_total_size(mut self, max_total_size: usize) -> Self {
+        self.inner = self.inner.max_total_size(max_total_size);
+        self
+    }
+
+    /// Refuse writes to the decoder once `max_buffered_len` bytes are buffered.
+    ///
+    /// See [`sans_io::FormDataBuilder::max_buffered_len`] for more details.
+    pub fn max_buffered_len(mut self, max_buffered_len: usize) -> Self {
+        self.inner = self.inner.max_buffered_len(max_buffered_len);
+        self
+    }
+
+    /// Limit the number of parts in the multipart stream to `max_parts`.
+    ///
+    /// See [`sans_io::FormDataBuilder::max_parts`] for more details.
+    pub fn max_parts(mut self, max_parts: usize) -> Self {
+        self.inner = self.inner.max_parts(max_parts);
+        self
+    }
+
+    /// Limit the size of the headers of each [`Part`] to `max_header_size` bytes.
+    ///
+    /// See [`sans_io::FormDataBuilder::max_header_size`] for more details.
+    pub fn max_header_size(mut self, max_header_size: usize) -> Self {
+        self.inner = self.inner.max_header_size(max_header_size);
+        self
+    }
+
+    /// Limit the length of the name and of the value of each header of each [`Part`].
+    ///
+    /// See [`sans_io::FormDataBuilder::max_header_field_size`] for more details.
+    pub fn max_header_field_size(
+        mut self,
+        max_header_name_size: usize,
+        max_header_value_size: usize,
+    ) -> Self {
+        self.inner = self
+            .inner
+            .max_header_field_size(max_header_name_size, max_header_value_size);
+        self
+    }
+
+    /// Accept bare `\n` line endings in addition to `\r\n`.
+    ///
+    /// See [`sans_io::FormDataBuilder::lenient`] for more details.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.inner = self.inner.lenient(lenient);
+        self
+    }
+
+    /// Match the boundary ignoring ASCII case.
+    ///
+    /// See [`sans_io::FormDataBuilder::boundary_ignore_ascii_case`] for more details.
+    pub fn boundary_ignore_ascii_case(mut self, boundary_ignore_ascii_case: bool) -> Self {
+        self.inner = self
+            .inner
+            .boundary_ignore_ascii_case(boundary_ignore_ascii_case);
+        self
+    }
+
+    /// Reject multipart streams which end without the closing boundary.
+    ///
+    /// See [`sans_io::FormDataBuilder::require_closing_boundary`] for more details.
+    pub fn require_closing_boundary(mut self, require_closing_boundary: bool) -> Self {
+        self.inner = self
+            .inner
+            .require_closing_boundary(require_closing_boundary);
+        self
+    }
+
+    /// Treat boundaries followed by an unexpected suffix as part of the body.
+    ///
+    /// See [`sans_io::FormDataBuilder::allow_false_boundaries`] for more details.
+    pub fn allow_false_boundaries(mut self, allow_false_boundaries: bool) -> Self {
+        self.inner = self.inner.allow_false_boundaries(allow_false_boundaries);
+        self
+    }
+
+    /// Unfold header values continuing on the next line.
+    ///
+    /// See [`sans_io::FormDataBuilder::unfold_headers`] for more details.
+    pub fn unfold_headers(mut self, unfold_headers: bool) -> Self {
+        self.inner = self.inner.unfold_headers(unfold_headers);
+        self
+    }
+
+    /// Strip the UTF-8 byte order mark some clients put at the start of text fields.
+    ///
+    /// See [`sans_io::FormDataBuilder::strip_bom`] for more details.
+    pub fn strip_bom(mut self, strip_bom: bool) -> Self {
+        self.inner = self.inner.strip_bom(strip_bom);
+        self
+    }
+
+    /// Accept and discard bytes after the closing boundary.
+    ///
+    /// See [`sans_io::FormDataBuilder::discard_epilogue`] for more details.
+    pub fn discard_epilogue(mut self, discard_epilogue: bool) -> Self {
+        self.inner = self.inner.discard_epilogue(discard_epilogue);
+        self
+    }
+
+    /// Collect the headers of each [`Part`] into its [`RawHeaders`].
+    ///
+    /// See [`sans_io::FormDataBuilder::collect_headers`] for more details.
+    pub fn collect_headers(mut self, collect_headers: bool) -> Self {
+        self.inner = self.inner.collect_headers(collect_headers);
+        self
+    }
+
+    /// Check that the body of each [`Part`] is as long as its `Content-Length` header declares.
+    ///
+    /// See [`sans_io::FormDataBuilder::validate_content_length`] for more details.
+    pub fn validate_content_length(mut self, validate_content_length: bool) -> Self {
+        self.inner = self.inner.validate_content_length(validate_content_length);
+        self
+    }
+
+    /// Decode the body of parts according to their `Content-Transfer-Encoding` header.
+    ///
+    /// See [`sans_io::FormDataBuilder::decode_transfer_encoding`] for more details.
+    #[cfg(feature = "cte")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cte")))]
+    pub fn decode_transfer_encoding(mut self, decode_transfer_encoding: bool) -> Self {
+        self.inner = self
+            .inner
+            .decode_transfer_encoding(decode_transfer_encoding);
+        self
+    }
+
+    /// Reserve `capacity` bytes of scratch space upfront.
+    ///
+    /// See [`sans_io::FormDataBuilder::capacity`] for more details.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.inner = self.inner.capacity(capacity);
+        self
+    }
+
+    /// Build a [`FormData`] reading from `stream`, with a boundary of `boundary`.
+    pub fn build<S>(&self, stream: S, boundary: &str) -> FormData<S> {
+        let inner_form =
+            plain_futures03::FormData::from_sans_io(stream, self.inner.build(boundary));
+        FormData::from_inner(inner_form)
+    }
 }
 
 impl<S> Stream for FormData<S>
 where
-    S: Stream<Item = Result<Bytes>> + Unpin,
+    S: Stream<Item = Result<Bytes>>,
 {
     type Item = Result<Part<S>>;
 
@@ -74,23 +643,48 @@ where
                 self.inner = Arc::new(TryLock::new(inner));
             }
         };
-        let mut inner = self.inner.try_lock().expect("TryLock was mem::forgotten");
+        let this = &mut *self;
+        let mut inner = this.inner.try_lock().expect("TryLock was mem::forgotten");
         let inner = inner.as_mut().expect("inner should never be None");
+        apply_pending(&mut this.pending, inner.as_mut().sans_io_mut());
 
-        match Pin::new(inner).poll_next(cx) {
+        // Drive the inner decoder until the next part, skipping the remaining
+        // body of the previous one, instead of rescheduling the task for every chunk
+        let poll = loop {
+            match inner.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(Read::Part(_)))) | Poll::Ready(Some(Ok(Read::PartEof))) => {}
+                poll => break poll,
+            }
+        };
+        if inner.is_terminated() {
+            this.terminated.store(true, Ordering::Release);
+        }
+
+        match poll {
             Poll::Pending => Poll::Pending,
-            Poll::Ready(Some(Ok(Read::NewPart { headers }))) => {
-                let inner = Arc::clone(&self.inner);
+            Poll::Ready(Some(Ok(Read::NewPart { headers, index }))) => {
+                let sans_io = inner.as_mut().sans_io_mut();
+                let capacity_hint = sans_io.content_length_hint().unwrap_or(0);
+                let content_length = sans_io.validated_content_length();
+                let inner = Arc::clone(&this.inner);
+                let parsed = headers.parse();
                 Poll::Ready(Some(Ok(Part {
                     headers,
+                    parsed,
+                    index,
+                    capacity_hint,
+                    content_length,
+                    bytes_read: 0,
+                    read_first: Bytes::new(),
+                    read_body: BytesMut::new(),
                     inner: Some(inner),
+                    terminated: Arc::clone(&this.terminated),
                 })))
             }
             Poll::Ready(Some(Ok(Read::Part(_)))) | Poll::Ready(Some(Ok(Read::PartEof))) => {
-                cx.waker().wake_by_ref();
-                Poll::Pending
+                unreachable!("part bodies are skipped above")
             }
-            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err))),
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err.into()))),
             Poll::Ready(None) => Poll::Ready(None),
         }
     }
@@ -98,16 +692,11 @@ where
 
 impl<S> FusedStream for FormData<S>
 where
-    S: Stream<Item = Result<Bytes>> + Unpin,
+    S: Stream<Item = Result<Bytes>>,
 {
     fn is_terminated(&self) -> bool {
-        match self.inner.try_lock() {
-            Some(inner) => match &*inner {
-                Some(inner) => inner.is_terminated(),
-                None => false,
-            },
-            None => false,
-        }
+        // Not going through `inner`, since a `Part` may be holding the lock
+        self.terminated.load(Ordering::Acquire)
     }
 }
 
@@ -117,24 +706,182 @@ impl<S> Debug for FormData<S> {
     }
 }
 
+impl<S> Stream for Parts<S>
+where
+    S: Stream<Item = Result<Bytes>>,
+{
+    type Item = Result<(Headers, Part<S>)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        match Pin::new(&mut self.form_data).poll_next(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some(Ok(part))) => match part.headers() {
+                Ok(headers) => Poll::Ready(Some(Ok((headers.clone(), part)))),
+                Err(err) => Poll::Ready(Some(Err(Error::new(ErrorKind::Other, err)))),
+            },
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err))),
+            Poll::Ready(None) => Poll::Ready(None),
+        }
+    }
+}
+
+impl<S> FusedStream for Parts<S>
+where
+    S: Stream<Item = Result<Bytes>>,
+{
+    fn is_terminated(&self) -> bool {
+        self.form_data.is_terminated()
+    }
+}
+
+impl<S> Debug for Parts<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Parts").finish()
+    }
+}
+
+impl<S> Stream for BufferedParts<S>
+where
+    S: Stream<Item = Result<Bytes>>,
+{
+    type Item = Result<(Headers, Bytes)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        loop {
+            let current = match &mut self.current {
+                Some(current) => current,
+                None => match Pin::new(&mut self.parts).poll_next(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Some(Ok((headers, part)))) => {
+                        self.current = Some(BufferedPart {
+                            headers,
+                            part,
+                            first: Bytes::new(),
+                            body: BytesMut::new(),
+                        });
+                        continue;
+                    }
+                    Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                    Poll::Ready(None) => return Poll::Ready(None),
+                },
+            };
+
+            match Pin::new(&mut current.part).poll_next(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(bytes))) => {
+                    let capacity_hint = current.part.capacity_hint;
+                    push_bytes(&mut current.first, &mut current.body, bytes, capacity_hint);
+                }
+                Poll::Ready(Some(Err(err))) => {
+                    self.current = None;
+                    return Poll::Ready(Some(Err(err)));
+                }
+                Poll::Ready(None) => {
+                    let current = self.current.take().expect("current is Some");
+                    let body = join_bytes(current.first, current.body.freeze());
+                    return Poll::Ready(Some(Ok((current.headers, body))));
+                }
+            }
+        }
+    }
+}
+
+impl<S> FusedStream for BufferedParts<S>
+where
+    S: Stream<Item = Result<Bytes>>,
+{
+    fn is_terminated(&self) -> bool {
+        self.current.is_none() && self.parts.is_terminated()
+    }
+}
+
+impl<S> Debug for BufferedParts<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BufferedParts").finish()
+    }
+}
+
 impl<S> Part<S> {
     /// Access the raw headers of this [`Part`].
     pub fn raw_headers(&self) -> &RawHeaders {
         &self.headers
     }
+
+    /// Access the parsed headers of this [`Part`].
+    ///
+    /// The headers are parsed once, when this [`Part`] is yielded by the [`FormData`].
+    /// Returns the error encountered while parsing them, if any.
+    pub fn headers(&self) -> std::result::Result<&Headers, headers::Error> {
+        self.parsed.as_ref().map_err(Clone::clone)
+    }
+
+    /// The index of this [`Part`] in the multipart stream, starting from 0.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The number of bytes of the body of this [`Part`] polled so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Decode the body of this [`Part`] as a nested multipart stream,
+    /// like the `multipart/mixed` bodies some clients use to upload
+    /// multiple files under the same field.
+    ///
+    /// Returns `Err(self)` if this [`Part`] doesn't have a nested body.
+    /// See [`RawHeaders::nested_boundary`] for more details.
+    // Giving back the `Part` is the whole point of the `Err` variant
+    #[allow(clippy::result_large_err)]
+    pub fn into_nested(self) -> std::result::Result<FormData<Self>, Self> {
+        match self.headers.nested_boundary().map(str::to_owned) {
+            Some(boundary) => Ok(FormData::new(self, &boundary)),
+            None => Err(self),
+        }
+    }
 }
 
-impl<S> Stream for Part<S>
+impl<S> Part<S>
 where
-    S: Stream<Item = Result<Bytes>> + Unpin,
+    S: Stream<Item = Result<Bytes>>,
 {
-    type Item = Result<Bytes>;
+    /// Read what's left of the body of this [`Part`] into a single [`Bytes`].
+    ///
+    /// This `Part` is terminated afterwards, so the next [`Part`]
+    /// can be polled out of the [`FormData`].
+    ///
+    /// If the returned future is dropped before completing, the bytes it
+    /// already read are kept in this `Part`, so calling this method again
+    /// resumes the read. Polling this `Part` as a `Stream` yields them first instead.
+    pub async fn read_to_end(&mut self) -> Result<Bytes> {
+        while let Some(bytes) = NextBody(self).await.transpose()? {
+            // Bodies made of a single `Bytes` aren't copied
+            push_bytes(
+                &mut self.read_first,
+                &mut self.read_body,
+                bytes,
+                self.capacity_hint,
+            );
+        }
 
-    /// Poll [`Bytes`] from this `Part`'s body.
+        let first = mem::take(&mut self.read_first);
+        let body = mem::take(&mut self.read_body);
+        Ok(join_bytes(first, body.freeze()))
+    }
+
+    /// Discard what's left of the body of this [`Part`].
     ///
-    /// This method yields an error if this is the non last `Part` yielded
-    /// by the [`FormData`] that yielded this part.
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    /// Dropping an unfinished `Part` has the same effect, but errors
+    /// in the discarded body are only reported by this method.
+    pub async fn skip(mut self) -> Result<()> {
+        while Next(&mut self).await.transpose()?.is_some() {}
+
+        Ok(())
+    }
+
+    /// Poll the next chunk of the body out of the decoder, skipping
+    /// what a cancelled [`Part::read_to_end`] left over.
+    fn poll_body(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes>>> {
         let inner_arc = match &self.inner {
             Some(inner_arc) => inner_arc,
             None => {
@@ -168,33 +915,141 @@ where
             }
         };
 
-        match Pin::new(inner).poll_next(cx) {
+        let poll = inner.as_mut().poll_next(cx);
+        if inner.is_terminated() {
+            self.terminated.store(true, Ordering::Release);
+        }
+
+        match poll {
             Poll::Pending => Poll::Pending,
-            Poll::Ready(Some(Ok(Read::Part(bytes)))) => Poll::Ready(Some(Ok(bytes))),
+            Poll::Ready(Some(Ok(Read::Part(bytes)))) => {
+                drop(inner_);
+
+                self.bytes_read += bytes.len() as u64;
+                Poll::Ready(Some(Ok(bytes)))
+            }
             Poll::Ready(Some(Ok(Read::PartEof))) | Poll::Ready(None) => {
                 drop(inner_);
 
                 self.inner = None;
                 Poll::Ready(None)
             }
-            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err))),
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err.into()))),
             Poll::Ready(Some(Ok(Read::NewPart { .. }))) => unreachable!(),
         }
     }
 }
 
+impl<S> Stream for Part<S>
+where
+    S: Stream<Item = Result<Bytes>>,
+{
+    type Item = Result<Bytes>;
+
+    /// Poll [`Bytes`] from this `Part`'s body.
+    ///
+    /// This method yields an error if this is the non last `Part` yielded
+    /// by the [`FormData`] that yielded this part.
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // Left over by a cancelled `read_to_end`
+        if !self.read_first.is_empty() || !self.read_body.is_empty() {
+            let first = mem::take(&mut self.read_first);
+            let body = mem::take(&mut self.read_body);
+            return Poll::Ready(Some(Ok(join_bytes(first, body.freeze()))));
+        }
+
+        self.poll_body(cx)
+    }
+
+    /// Every yielded [`Bytes`] is non-empty, so the upper bound is also
+    /// the number of bytes left in the body of this `Part`.
+    ///
+    /// The upper bound is only known if the `Content-Length` header of this `Part`
+    /// is being validated. See [`FormData::set_validate_content_length`].
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Left over by a cancelled `read_to_end`, and yielded again
+        let pending = self.read_first.len() + self.read_body.len();
+        if self.inner.is_none() {
+            return (0, Some(pending));
+        }
+
+        match self.content_length {
+            Some(content_length) => {
+                let remaining = content_length.saturating_sub(self.bytes_read) + pending as u64;
+                // Leave room for the error yielded if the body is shorter than declared
+                let upper = usize::try_from(remaining)
+                    .ok()
+                    .and_then(|n| n.checked_add(1));
+                (0, upper)
+            }
+            None => (0, None),
+        }
+    }
+}
+
 impl<S> FusedStream for Part<S>
 where
-    S: Stream<Item = Result<Bytes>> + Unpin,
+    S: Stream<Item = Result<Bytes>>,
 {
     fn is_terminated(&self) -> bool {
-        self.inner.is_none()
+        self.inner.is_none() && self.read_first.is_empty() && self.read_body.is_empty()
     }
 }
 
 impl<S> Debug for Part<S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("Part").finish()
+        let mut debug = f.debug_struct("Part");
+        match &self.parsed {
+            Ok(headers) => debug
+                .field("name", &headers.name)
+                .field("filename", &headers.filename),
+            Err(_) => debug.field("headers", &self.headers.iter().count()),
+        };
+        debug.field("terminated", &self.inner.is_none()).finish()
+    }
+}
+
+/// Append `bytes` to a body made of `first` followed by `body`.
+///
+/// Bodies made of a single `Bytes` are kept in `first`, without copying them.
+/// Otherwise `body` is pre-sized to `capacity_hint` bytes.
+fn push_bytes(first: &mut Bytes, body: &mut BytesMut, bytes: Bytes, capacity_hint: usize) {
+    if first.is_empty() && body.is_empty() {
+        *first = bytes;
+    } else {
+        if body.is_empty() {
+            body.reserve(capacity_hint);
+        }
+        body.put(mem::take(first));
+        body.put(bytes);
+    }
+}
+
+/// Future resolving to the next chunk of the body of a [`Part`], see [`Part::poll_body`]
+struct NextBody<'a, S>(&'a mut Part<S>);
+
+impl<'a, S> Future for NextBody<'a, S>
+where
+    S: Stream<Item = Result<Bytes>>,
+{
+    type Output = Option<Result<Bytes>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.poll_body(cx)
+    }
+}
+
+/// Future resolving to the next item of a `Stream`
+struct Next<'a, S>(&'a mut S);
+
+impl<'a, S> Future for Next<'a, S>
+where
+    S: Stream + Unpin,
+{
+    type Output = Option<S::Item>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.0).poll_next(cx)
     }
 }
 
@@ -204,11 +1059,13 @@ mod tests {
 
     #[test]
     fn assertions() {
+        use std::marker::PhantomPinned;
+
         fn assert_send<T: Send>() {}
         fn assert_sync<T: Sync>() {}
         fn assert_unpin<T: Unpin>() {}
 
-        struct PerfectStream;
+        struct PerfectStream(PhantomPinned);
 
         impl Stream for PerfectStream {
             type Item = Result<Bytes>;
@@ -226,4 +1083,76 @@ mod tests {
         assert_sync::<Part<PerfectStream>>();
         assert_unpin::<Part<PerfectStream>>();
     }
+
+    #[tokio::test]
+    async fn terminated_while_locked() {
+        let body =
+            "--abcd\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n--abcd--\r\n";
+        let stream = Iter(Some(Ok(Bytes::from(body))));
+        let mut form_data = FormData::new(stream, "abcd");
+
+        let mut part = Next(&mut form_data).await.unwrap().unwrap();
+        assert_eq!(part.read_to_end().await.unwrap(), "bar");
+        assert!(!form_data.is_terminated());
+
+        assert!(Next(&mut form_data).await.is_none());
+
+        // Simulate a `Part` being polled from another thread
+        let _guard = form_data.inner.try_lock().unwrap();
+        assert!(form_data.is_terminated());
+        assert!(part.is_terminated());
+    }
+
+    #[tokio::test]
+    async fn configure_while_locked() {
+        let body =
+            "--abcd\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n--abcd--\r\n";
+        let stream = Iter(Some(Ok(Bytes::from(body))));
+        let mut form_data = FormData::new(stream, "abcd");
+
+        // Simulate a `Part` being polled from another thread
+        let inner = Arc::clone(&form_data.inner);
+        let guard = inner.try_lock().unwrap();
+        form_data.set_max_part_size(2);
+        drop(guard);
+        drop(inner);
+
+        let mut part = Next(&mut form_data).await.unwrap().unwrap();
+        assert!(part.read_to_end().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn debug_part() {
+        let body = "--abcd\r\ncontent-disposition: form-data; name=\"foo\"; filename=\"a.txt\"\r\n\r\nbar\r\n\
+                    --abcd\r\ncontent-type: text/plain\r\n\r\nbaz\r\n--abcd--\r\n";
+        let stream = Iter(Some(Ok(Bytes::from(body))));
+        let mut form_data = FormData::new(stream, "abcd");
+
+        let mut part1 = Next(&mut form_data).await.unwrap().unwrap();
+        assert_eq!(
+            format!("{:?}", part1),
+            r#"Part { name: "foo", filename: Some("a.txt"), terminated: false }"#
+        );
+        part1.read_to_end().await.unwrap();
+        assert_eq!(
+            format!("{:?}", part1),
+            r#"Part { name: "foo", filename: Some("a.txt"), terminated: true }"#
+        );
+
+        let part2 = Next(&mut form_data).await.unwrap().unwrap();
+        assert_eq!(
+            format!("{:?}", part2),
+            "Part { headers: 1, terminated: false }"
+        );
+    }
+
+    struct Iter(Option<Result<Bytes>>);
+
+    impl Stream for Iter {
+        type Item = Result<Bytes>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Option<Self::Item>> {
+            Poll::Ready(self.0.take())
+        }
+    }
 }
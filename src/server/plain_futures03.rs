@@ -1,4 +1,37 @@
-use std::io::{Error, ErrorKind, Result};
+//! `futures` `Stream` 0.3 low-level multipart decoder.
+//!
+//! Unlike [`owned_futures03`](super::owned_futures03), the decoder yields the
+//! parts as a flat sequence of [`Read`] items and keeps the error of the
+//! underlying `Stream`, instead of converting it into an [`io::Error`].
+//!
+//! ```rust
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use bytes::Bytes;
+//! use futures_util::stream::{self, TryStreamExt};
+//! use multiparty::server::plain_futures03::{FormData, FormDataError, Read};
+//!
+//! #[derive(Debug)]
+//! struct MyError;
+//!
+//! let body = "--boundary\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n--boundary--\r\n";
+//! let stream = stream::iter(vec![Ok::<_, MyError>(Bytes::from_static(body.as_bytes()))]);
+//! let mut form_data = Box::pin(FormData::new(stream, "boundary"));
+//!
+//! while let Some(read) = form_data.try_next().await.map_err(|err| match err {
+//!     FormDataError::Decode(err) => err.to_string(),
+//!     FormDataError::Stream(MyError) => "stream error".to_owned(),
+//! })? {
+//!     if let Read::Part(bytes) = read {
+//!         assert_eq!(bytes, "bar");
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display};
+use std::io;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
@@ -10,14 +43,72 @@ use crate::headers::RawHeaders;
 
 use super::sans_io::{self, Read as InnerRead};
 
+/// An item read from [`FormData`]
 #[derive(Debug)]
 pub enum Read {
-    NewPart { headers: RawHeaders },
+    /// The beginning of a new part.
+    NewPart {
+        /// The headers inside the new part
+        headers: RawHeaders,
+        /// The index of the new part, starting from 0
+        index: usize,
+    },
+    /// [`Bytes`] from the current part.
     Part(Bytes),
+    /// The current part has ended.
     PartEof,
 }
 
+/// Error yielded by [`FormData`].
+///
+/// `E` is the error type of the underlying `Stream`.
+#[derive(Debug)]
+pub enum FormDataError<E> {
+    /// The multipart stream couldn't be decoded.
+    Decode(sans_io::Error),
+    /// The underlying `Stream` yielded an error.
+    Stream(E),
+}
+
+impl<E: Display> Display for FormDataError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(err) => Display::fmt(err, f),
+            Self::Stream(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E: StdError + 'static> StdError for FormDataError<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Decode(err) => Some(err),
+            Self::Stream(err) => Some(err),
+        }
+    }
+}
+
+impl<E> From<sans_io::Error> for FormDataError<E> {
+    fn from(err: sans_io::Error) -> Self {
+        Self::Decode(err)
+    }
+}
+
+/// Stream errors are returned as is, while decoding errors are
+/// wrapped into an [`io::Error`] of kind [`io::ErrorKind::Other`].
+impl From<FormDataError<io::Error>> for io::Error {
+    fn from(err: FormDataError<io::Error>) -> Self {
+        match err {
+            FormDataError::Decode(err) => err.into(),
+            FormDataError::Stream(err) => err,
+        }
+    }
+}
+
 pin_project! {
+    /// A `Stream` of [`Read`] items.
+    ///
+    /// Works with any `Stream<Item = Result<Bytes, E>>`.
     pub struct FormData<S> {
         #[pin]
         stream: S,
@@ -26,17 +117,41 @@ pin_project! {
 }
 
 impl<S> FormData<S> {
+    /// Construct a new `FormData` from a `Stream` of [`Bytes`] and a `boundary`.
     pub fn new(stream: S, boundary: &str) -> Self {
         let inner = sans_io::FormData::new(boundary);
         Self { stream, inner }
     }
+
+    /// Construct a new `FormData` accepting at most `max_headers` headers per part.
+    ///
+    /// See [`sans_io::FormData::with_max_headers`] for more details.
+    pub fn with_max_headers(stream: S, boundary: &str, max_headers: usize) -> Self {
+        let inner = sans_io::FormData::with_max_headers(boundary, max_headers);
+        Self { stream, inner }
+    }
+
+    /// Construct a new `FormData` from an already configured [`sans_io::FormData`].
+    pub fn from_sans_io(stream: S, inner: sans_io::FormData) -> Self {
+        Self { stream, inner }
+    }
+
+    /// Get the inner [`sans_io::FormData`].
+    pub fn sans_io(&self) -> &sans_io::FormData {
+        &self.inner
+    }
+
+    /// Get the inner [`sans_io::FormData`] mutably.
+    pub fn sans_io_mut(self: Pin<&mut Self>) -> &mut sans_io::FormData {
+        self.project().inner
+    }
 }
 
-impl<S> Stream for FormData<S>
+impl<S, E> Stream for FormData<S>
 where
-    S: Stream<Item = Result<Bytes>>,
+    S: Stream<Item = Result<Bytes, E>>,
 {
-    type Item = Result<Read>;
+    type Item = Result<Read, FormDataError<E>>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let mut this = self.project();
@@ -51,7 +166,9 @@ where
 
                             // continue
                         }
-                        Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                        Poll::Ready(Some(Err(err))) => {
+                            return Poll::Ready(Some(Err(FormDataError::Stream(err))))
+                        }
                         Poll::Ready(None) => {
                             this.inner.write_eof();
 
@@ -59,8 +176,8 @@ where
                         }
                     };
                 }
-                Ok(InnerRead::NewPart { headers }) => {
-                    return Poll::Ready(Some(Ok(Read::NewPart { headers })))
+                Ok(InnerRead::NewPart { headers, index }) => {
+                    return Poll::Ready(Some(Ok(Read::NewPart { headers, index })))
                 }
                 Ok(InnerRead::Part(bytes)) => return Poll::Ready(Some(Ok(Read::Part(bytes)))),
                 Ok(InnerRead::PartEof) => return Poll::Ready(Some(Ok(Read::PartEof))),
@@ -68,17 +185,23 @@ where
                     // continue
                 }
                 Ok(InnerRead::Eof) => return Poll::Ready(None),
-                Err(err) => return Poll::Ready(Some(Err(Error::new(ErrorKind::Other, err)))),
+                Err(err) => return Poll::Ready(Some(Err(FormDataError::Decode(err)))),
             }
         }
     }
 }
 
-impl<S> FusedStream for FormData<S>
+impl<S, E> FusedStream for FormData<S>
 where
-    S: Stream<Item = Result<Bytes>>,
+    S: Stream<Item = Result<Bytes, E>>,
 {
     fn is_terminated(&self) -> bool {
         self.inner.is_eof()
     }
 }
+
+impl<S> Debug for FormData<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FormData").finish()
+    }
+}
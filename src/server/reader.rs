@@ -0,0 +1,100 @@
+//! Driver shared by the decoders reading from an IO reader.
+
+use std::io::{self, Error, ErrorKind};
+use std::task::Poll;
+
+use bytes::{Buf, Bytes, BytesMut};
+
+use super::sans_io::{self, Read};
+
+/// The number of bytes read from the underlying reader at a time
+const READ_SIZE: usize = 8 * 1024;
+
+/// A [`sans_io::FormData`] fed by a reader.
+pub(crate) struct Driver {
+    inner: sans_io::FormData,
+    buf: BytesMut,
+}
+
+/// The body of the current part, as read by a reader.
+pub(crate) struct PartBody {
+    chunk: Bytes,
+    done: bool,
+}
+
+impl Driver {
+    pub(crate) fn new(inner: sans_io::FormData) -> Self {
+        Self {
+            inner,
+            buf: BytesMut::new(),
+        }
+    }
+
+    /// Drive the decoder until it yields something other than
+    /// [`Read::NeedsWrite`] or [`Read::None`].
+    ///
+    /// `fill` reads into the given buffer, returning how many bytes have been read,
+    /// with `0` meaning that the end of the reader has been reached.
+    pub(crate) fn poll_read<F>(&mut self, mut fill: F) -> Poll<io::Result<Read>>
+    where
+        F: FnMut(&mut [u8]) -> Poll<io::Result<usize>>,
+    {
+        loop {
+            match self.inner.read() {
+                Ok(Read::NeedsWrite) => {
+                    self.buf.resize(READ_SIZE, 0);
+                    let read = match fill(&mut self.buf) {
+                        Poll::Pending => return Poll::Pending,
+                        Poll::Ready(Ok(read)) => read,
+                        Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                    };
+                    self.buf.truncate(read);
+
+                    if read == 0 {
+                        self.inner.write_eof();
+                    } else {
+                        let bytes = self.buf.split().freeze();
+                        self.inner.write(bytes).expect("we've been told to write");
+                    }
+                }
+                Ok(Read::None) => {
+                    // continue
+                }
+                Ok(read) => return Poll::Ready(Ok(read)),
+                Err(err) => return Poll::Ready(Err(Error::new(ErrorKind::Other, err))),
+            }
+        }
+    }
+}
+
+impl PartBody {
+    pub(crate) fn new() -> Self {
+        Self {
+            chunk: Bytes::new(),
+            done: false,
+        }
+    }
+
+    /// Copy the body into `buf`, calling `next` for more of it.
+    ///
+    /// `next` is one of the [`Driver::poll_read`] calls.
+    pub(crate) fn poll_read<F>(&mut self, buf: &mut [u8], mut next: F) -> Poll<io::Result<usize>>
+    where
+        F: FnMut() -> Poll<io::Result<Read>>,
+    {
+        while self.chunk.is_empty() && !self.done {
+            match next() {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(Read::Part(bytes))) => self.chunk = bytes,
+                Poll::Ready(Ok(Read::PartEof)) | Poll::Ready(Ok(Read::Eof)) => self.done = true,
+                Poll::Ready(Ok(_)) => unreachable!(),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            }
+        }
+
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk[..len]);
+        self.chunk.advance(len);
+        Poll::Ready(Ok(len))
+    }
+}
@@ -7,27 +7,200 @@
 //! be better served by one of the high level wrappers from the
 //! [`server`] module.
 //!
+//! # Example
+//!
+//! Driving [`FormData`] by hand, feeding it chunks as it asks for them.
+//! The chunks could just as well come from a socket or a file.
+//!
+//! ```rust
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use bytes::Bytes;
+//! use multiparty::server::sans_io::{FormData, Read};
+//!
+//! let mut chunks = vec![
+//!     Bytes::from_static(b"--abcd\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nb"),
+//!     Bytes::from_static(b"ar\r\n--abcd\r\ncontent-disposition: form-data; name=\"baz\""),
+//!     Bytes::from_static(b"\r\n\r\nqux\r\n--abcd--\r\n"),
+//! ]
+//! .into_iter();
+//!
+//! let mut form_data = FormData::new("abcd");
+//! let mut parts = Vec::new();
+//! loop {
+//!     match form_data.read()? {
+//!         Read::NeedsWrite => match chunks.next() {
+//!             Some(chunk) => form_data.write(chunk).expect("eof hasn't been written yet"),
+//!             None => form_data.write_eof(),
+//!         },
+//!         Read::NewPart { headers, .. } => parts.push((headers.parse()?.name, Vec::new())),
+//!         Read::Part(bytes) => {
+//!             let (_, body) = parts.last_mut().expect("Part is preceded by NewPart");
+//!             body.extend_from_slice(&bytes);
+//!         }
+//!         Read::PartEof | Read::None => {}
+//!         Read::Eof => break,
+//!     }
+//! }
+//!
+//! assert_eq!(
+//!     parts,
+//!     [
+//!         ("foo".to_owned(), b"bar".to_vec()),
+//!         ("baz".to_owned(), b"qux".to_vec()),
+//!     ]
+//! );
+//! assert!(!form_data.was_truncated());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`decode_all`] is a shortcut for bodies which are already in memory.
+//!
 //! [`server`]: crate::server
 
+use std::collections::VecDeque;
 use std::error::Error as StdError;
 use std::fmt::{self, Debug, Display};
+use std::io;
+use std::iter;
 use std::mem;
+#[cfg(feature = "cte")]
+use std::str;
 
-use bytes::{Buf, Bytes};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 
+#[cfg(feature = "cte")]
+use super::cte::Base64Decoder;
 use crate::boundary::Boundary;
 use crate::headers::RawHeaders;
-use crate::utils::{find_bytes, find_bytes_split, join_bytes, starts_with_between};
+use crate::search::{
+    find_bytes, find_bytes_chain, find_bytes_chain_ignore_ascii_case, find_bytes_ignore_ascii_case,
+    starts_with_between, starts_with_chain, starts_with_chain_ignore_ascii_case,
+};
+use crate::utils::join_bytes;
+
+/// The default maximum number of headers allowed in each part.
+pub const DEFAULT_MAX_HEADERS: usize = 128;
+
+/// The default maximum length of the name and of the value of each header.
+pub const DEFAULT_MAX_HEADER_FIELD_SIZE: usize = 8 * 1024;
+
+/// The number of headers the decoder initially makes space for.
+///
+/// Parts with at most this many headers are decoded without allocating
+/// the `httparse` scratch space on the heap. The scratch space is doubled
+/// each time `httparse` runs out of space, until `max_headers` is reached.
+const INITIAL_HEADERS: usize = 8;
+
+/// The UTF-8 byte order mark
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// The callback set through [`FormData::set_on_event`]
+type OnEvent = Box<dyn FnMut(Event<'_>) + Send + Sync>;
 
 /// Sans IO multipart decoder
 pub struct FormData {
     boundary: Boundary,
     bytes1: Bytes,
     bytes2: Bytes,
+    /// [`Bytes`] written while both `bytes1` and `bytes2` were full
+    queue: VecDeque<Bytes>,
+    /// Space for joining buffered [`Bytes`], reused once the joined [`Bytes`] are dropped
+    scratch: BytesMut,
+    /// Storage for the headers of the next part, see [`FormData::recycle_headers`]
+    headers_buf: Vec<(Bytes, Bytes)>,
 
+    max_headers: usize,
+    max_part_size: Option<usize>,
+    max_field_size: Option<usize>,
+    max_file_size: Option<usize>,
+    max_total_size: Option<usize>,
+    max_buffered_len: Option<usize>,
+    max_parts: Option<usize>,
+    max_header_size: Option<usize>,
+    max_header_name_size: usize,
+    max_header_value_size: usize,
+    lenient: bool,
+    boundary_ignore_ascii_case: bool,
+    unfold_headers: bool,
+    strip_bom: bool,
+    collect_headers: bool,
+    discard_epilogue: bool,
+    require_closing_boundary: bool,
+    allow_false_boundaries: bool,
+    validate_content_length: bool,
+    #[cfg(feature = "cte")]
+    decode_transfer_encoding: bool,
+    on_event: Option<OnEvent>,
+
+    /// [`FormData::write_eof`] has been called
+    eof: bool,
+    /// [`Read::NeedsWrite`] has been returned since the last write
+    needs_write: bool,
+    /// [`Event::Eof`] has already been emitted
+    eof_event_emitted: bool,
+    /// The stream ended without the closing boundary
+    truncated: bool,
+
+    #[cfg(feature = "cte")]
+    base64: Option<Base64Decoder>,
+    /// The UTF-8 BOM at the start of the body of the current part has to be stripped
+    strip_bom_pending: bool,
+    /// The offset of the first line of the buffered headers which hasn't been terminated yet
+    headers_scanned: usize,
+    /// The first boundary has been found
+    saw_boundary: bool,
+    /// In lenient mode, the `\r` before the boundary being checked has been stripped
+    carriage_return_stripped: bool,
+    parts: usize,
+    /// The limit on the size of the body of the current part
+    part_limit: Option<usize>,
+    /// The `Content-Length` of the current part
+    part_content_length: Option<u64>,
+    part_len: usize,
+    current_part_len: u64,
+    consumed: u64,
     state: State,
 }
 
+/// A builder for configuring [`FormData`].
+///
+/// Returned by [`FormData::builder`].
+///
+/// ```
+/// use multiparty::server::sans_io::FormData;
+///
+/// let form_data = FormData::builder()
+///     .max_part_size(1024 * 1024)
+///     .max_parts(16)
+///     .lenient(true)
+///     .build("abcdef");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FormDataBuilder {
+    max_headers: Option<usize>,
+    max_part_size: Option<usize>,
+    max_field_size: Option<usize>,
+    max_file_size: Option<usize>,
+    max_total_size: Option<usize>,
+    max_buffered_len: Option<usize>,
+    max_parts: Option<usize>,
+    max_header_size: Option<usize>,
+    max_header_field_size: Option<(usize, usize)>,
+    lenient: bool,
+    boundary_ignore_ascii_case: bool,
+    require_closing_boundary: bool,
+    allow_false_boundaries: bool,
+    unfold_headers: bool,
+    strip_bom: bool,
+    discard_epilogue: bool,
+    collect_headers: Option<bool>,
+    validate_content_length: bool,
+    #[cfg(feature = "cte")]
+    decode_transfer_encoding: bool,
+    capacity: usize,
+}
+
 /// An item read from [`FormData`]
 #[derive(Debug)]
 pub enum Read {
@@ -37,6 +210,8 @@ pub enum Read {
     NewPart {
         /// The headers inside the new part
         headers: RawHeaders,
+        /// The index of the new part, starting from 0
+        index: usize,
     },
     /// [`Bytes`] from the current part.
     Part(Bytes),
@@ -49,38 +224,256 @@ pub enum Read {
     Eof,
 }
 
+/// An event emitted by [`FormData::read`].
+///
+/// See [`FormData::set_on_event`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum Event<'a> {
+    /// A new part has started.
+    NewPart {
+        /// The headers of the new part
+        headers: &'a RawHeaders,
+        /// The index of the new part, starting from 0
+        index: usize,
+    },
+    /// Bytes from the body of the current part have been yielded.
+    Part {
+        /// The number of bytes
+        len: usize,
+    },
+    /// The current part has ended.
+    PartEof,
+    /// The multipart stream has ended.
+    Eof,
+}
+
+/// The kind of [`Read`] that [`FormData`] is about to yield.
+///
+/// Returned by [`FormData::peek`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadKind {
+    /// [`Read::NeedsWrite`]
+    NeedsWrite,
+    /// [`Read::NewPart`]
+    NewPart,
+    /// [`Read::Part`]
+    Part,
+    /// [`Read::PartEof`]
+    PartEof,
+    /// [`Read::Eof`]
+    Eof,
+}
+
 /// An error while decoding the multipart stream
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 #[non_exhaustive]
 pub enum Error {
+    /// The boundary is empty, which isn't allowed by RFC 2046.
+    EmptyBoundary,
     /// The binary suffix is supposed to either be `\r\n` or `--`,
     /// but a different suffix was found.
-    UnexpectedBoundarySuffix,
+    UnexpectedBoundarySuffix {
+        /// The offset of the suffix from the start of the multipart stream
+        offset: u64,
+    },
     /// The end of stream was reached on a part which isn't supposed to be truncated.
     UnexpectedEof,
     /// An error was returned by the headers decoder.
-    Headers(httparse::Error),
+    ///
+    /// This is also what header values containing an unencoded line break
+    /// usually cause, as the rest of the value ends up on a line of its own.
+    /// Those which happen to look like a header are caught when parsing the
+    /// headers instead, as the quoted string they're in is never closed.
+    Headers {
+        /// The error returned by `httparse`
+        error: httparse::Error,
+        /// The offset of the headers from the start of the multipart stream
+        offset: u64,
+    },
+    /// The body of the current part is bigger than the configured limit.
+    PartTooLarge {
+        /// The maximum size of the body of a part
+        limit: usize,
+    },
+    /// The length of the body of the current part doesn't match its `Content-Length` header.
+    PartLengthMismatch {
+        /// The length declared by the `Content-Length` header
+        expected: u64,
+        /// The length of the body, or the length read so far if it's longer than declared
+        actual: u64,
+    },
+    /// The multipart stream is bigger than the configured limit.
+    TotalSizeExceeded {
+        /// The maximum size of the multipart stream
+        limit: usize,
+    },
+    /// The multipart stream contains more parts than the configured limit.
+    TooManyParts {
+        /// The maximum number of parts
+        limit: usize,
+    },
+    /// The headers of the current part are bigger than the configured limit.
+    HeadersTooLarge {
+        /// The maximum size of the headers of a part
+        limit: usize,
+    },
+    /// The name or the value of a header of the current part is longer than the configured limit.
+    HeaderFieldTooLarge {
+        /// The length of the name of the header
+        name_len: usize,
+        /// The length of the value of the header
+        value_len: usize,
+    },
+    /// The `Content-Transfer-Encoding` of the current part isn't supported.
+    #[cfg(feature = "cte")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cte")))]
+    UnsupportedTransferEncoding,
+    /// The body of the current part isn't valid base64.
+    #[cfg(feature = "cte")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cte")))]
+    InvalidBase64,
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::UnexpectedBoundarySuffix => f.write_str("unexpected boundary suffix"),
+            Self::EmptyBoundary => f.write_str("empty boundary"),
+            Self::UnexpectedBoundarySuffix { offset } => {
+                write!(f, "unexpected boundary suffix at byte {}", offset)
+            }
             Self::UnexpectedEof => f.write_str("unexpected eof"),
-            Self::Headers(_) => f.write_str("header parsing error"),
+            Self::Headers { offset, .. } => write!(f, "header parsing error at byte {}", offset),
+            Self::PartTooLarge { limit } => {
+                write!(f, "part is larger than the limit of {} bytes", limit)
+            }
+            Self::PartLengthMismatch { expected, actual } => write!(
+                f,
+                "part is {} bytes long, but its Content-Length is {}",
+                actual, expected
+            ),
+            Self::TotalSizeExceeded { limit } => {
+                write!(f, "body is larger than the limit of {} bytes", limit)
+            }
+            Self::TooManyParts { limit } => write!(f, "more than {} parts", limit),
+            Self::HeadersTooLarge { limit } => {
+                write!(f, "headers are larger than the limit of {} bytes", limit)
+            }
+            Self::HeaderFieldTooLarge {
+                name_len,
+                value_len,
+            } => write!(
+                f,
+                "header with a {} bytes long name and a {} bytes long value is too large",
+                name_len, value_len
+            ),
+            #[cfg(feature = "cte")]
+            Self::UnsupportedTransferEncoding => f.write_str("unsupported transfer encoding"),
+            #[cfg(feature = "cte")]
+            Self::InvalidBase64 => f.write_str("invalid base64 part body"),
         }
     }
 }
 
+impl Error {
+    /// Get the [`Error`] wrapped by an [`io::Error`].
+    ///
+    /// Decoders returning an [`io::Error`], like the one in `owned_futures03`,
+    /// wrap decoding errors into an [`io::Error`] of kind [`io::ErrorKind::Other`].
+    /// Returns `None` for every other [`io::Error`], like the ones coming
+    /// from the underlying reader or stream.
+    pub fn from_io_error(err: &io::Error) -> Option<&Self> {
+        err.get_ref()?.downcast_ref()
+    }
+}
+
+/// Wraps the [`Error`] into an [`io::Error`] of kind [`io::ErrorKind::Other`],
+/// which can be retrieved through [`Error::from_io_error`].
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        io::Error::new(io::ErrorKind::Other, err)
+    }
+}
+
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
-            Self::UnexpectedBoundarySuffix | Self::UnexpectedEof => None,
-            Self::Headers(err) => Some(err),
+            Self::EmptyBoundary
+            | Self::UnexpectedBoundarySuffix { .. }
+            | Self::UnexpectedEof
+            | Self::PartTooLarge { .. }
+            | Self::PartLengthMismatch { .. }
+            | Self::TotalSizeExceeded { .. }
+            | Self::TooManyParts { .. }
+            | Self::HeadersTooLarge { .. }
+            | Self::HeaderFieldTooLarge { .. } => None,
+            #[cfg(feature = "cte")]
+            Self::UnsupportedTransferEncoding | Self::InvalidBase64 => None,
+            Self::Headers { error, .. } => Some(error),
+        }
+    }
+}
+
+/// Error returned by [`FormData::write`] when it can't accept more [`Bytes`].
+///
+/// The rejected [`Bytes`] are kept, and can be taken back
+/// through [`WriteError::into_bytes`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteError {
+    kind: WriteErrorKind,
+    bytes: Bytes,
+}
+
+/// The reason why [`FormData::write`] returned a [`WriteError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WriteErrorKind {
+    /// [`FormData::write_eof`] has already been called.
+    EofWritten,
+    /// The closing boundary has already been read, so the multipart stream
+    /// has ended. See [`FormData::set_discard_epilogue`].
+    Finished,
+    /// [`FormData::set_max_buffered_len`] bytes are already buffered.
+    Full,
+}
+
+impl WriteError {
+    /// The reason why the [`Bytes`] have been rejected.
+    pub fn kind(&self) -> WriteErrorKind {
+        self.kind
+    }
+
+    /// Take back the rejected [`Bytes`].
+    pub fn into_bytes(self) -> Bytes {
+        self.bytes
+    }
+}
+
+impl Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            WriteErrorKind::EofWritten => f.write_str("write after write_eof"),
+            WriteErrorKind::Finished => f.write_str("write after the end of the multipart stream"),
+            WriteErrorKind::Full => f.write_str("write while the buffer is full"),
         }
     }
 }
 
+impl StdError for WriteError {}
+
+/// Error returned by [`FormData::set_boundary`] when bytes
+/// have already been given to the [`FormData`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetBoundaryError(());
+
+impl Display for SetBoundaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the boundary can't be set after writing to the decoder")
+    }
+}
+
+impl StdError for SetBoundaryError {}
+
 /// Internal state of [`FormData`]
 #[derive(PartialEq)]
 enum State {
@@ -94,47 +487,579 @@ enum State {
 
 impl FormData {
     /// Create a new instance of [`FormData`] with a boundary of `boundary`.
+    ///
+    /// The boundary isn't validated, except for empty boundaries
+    /// which make [`FormData::read`] return [`Error::EmptyBoundary`].
+    /// Use [`Boundary::try_new`] beforehand to reject every boundary
+    /// not allowed by RFC 2046.
+    ///
+    /// [`Boundary::try_new`]: crate::boundary::Boundary::try_new
     pub fn new(boundary: &str) -> Self {
+        Self::with_max_headers(boundary, DEFAULT_MAX_HEADERS)
+    }
+
+    /// Create a new instance of [`FormData`] with a boundary of `boundary`,
+    /// accepting at most `max_headers` headers in each part.
+    ///
+    /// Parts with more than `max_headers` headers make [`FormData::read`]
+    /// return [`Error::Headers`]. Parts with more than 8 headers
+    /// require the headers scratch space to be allocated.
+    pub fn with_max_headers(boundary: &str, max_headers: usize) -> Self {
         let boundary = Boundary::new(boundary);
         Self {
             boundary,
             bytes1: Bytes::new(),
             bytes2: Bytes::new(),
+            queue: VecDeque::new(),
+            scratch: BytesMut::new(),
+            headers_buf: Vec::new(),
+            max_headers,
+            max_part_size: None,
+            max_field_size: None,
+            max_file_size: None,
+            max_total_size: None,
+            max_buffered_len: None,
+            max_parts: None,
+            max_header_size: None,
+            max_header_name_size: DEFAULT_MAX_HEADER_FIELD_SIZE,
+            max_header_value_size: DEFAULT_MAX_HEADER_FIELD_SIZE,
+            lenient: false,
+            boundary_ignore_ascii_case: false,
+            unfold_headers: false,
+            strip_bom: false,
+            collect_headers: true,
+            discard_epilogue: false,
+            require_closing_boundary: false,
+            allow_false_boundaries: false,
+            validate_content_length: false,
+            #[cfg(feature = "cte")]
+            decode_transfer_encoding: false,
+            on_event: None,
+            eof: false,
+            needs_write: false,
+            eof_event_emitted: false,
+            truncated: false,
+            #[cfg(feature = "cte")]
+            base64: None,
+            strip_bom_pending: false,
+            headers_scanned: 0,
+            saw_boundary: false,
+            carriage_return_stripped: false,
+            parts: 0,
+            part_limit: None,
+            part_content_length: None,
+            part_len: 0,
+            current_part_len: 0,
+            consumed: 0,
             state: State::Uninit,
         }
     }
 
+    /// Create a [`FormDataBuilder`], for configuring multiple options at once.
+    pub fn builder() -> FormDataBuilder {
+        FormDataBuilder::new()
+    }
+
+    /// Create a new instance of [`FormData`] whose boundary isn't known yet.
+    ///
+    /// The boundary has to be set through [`FormData::set_boundary`] before
+    /// writing anything, otherwise [`FormData::read`] returns [`Error::EmptyBoundary`].
+    ///
+    /// ```rust
+    /// use multiparty::server::sans_io::FormData;
+    ///
+    /// let mut form_data = FormData::pending();
+    /// // ...find out the boundary
+    /// form_data.set_boundary("abcd").unwrap();
+    /// assert_eq!(form_data.boundary(), "abcd");
+    /// ```
+    pub fn pending() -> Self {
+        Self::new("")
+    }
+
+    /// Create a new instance of [`FormData`] with a boundary of `boundary`,
+    /// reserving `capacity` bytes of scratch space upfront.
+    ///
+    /// The scratch space is used for joining [`Bytes`] when the headers of
+    /// a part are split across multiple writes. It's reused once the headers
+    /// of the previous part have been dropped, instead of allocating every time.
+    pub fn with_capacity(boundary: &str, capacity: usize) -> Self {
+        let mut form_data = Self::new(boundary);
+        form_data.scratch = BytesMut::with_capacity(capacity);
+        form_data
+    }
+
+    /// Limit the size of the body of each part to `max_part_size` bytes.
+    ///
+    /// Parts with a bigger body make [`FormData::read`] return
+    /// [`Error::PartTooLarge`] instead of yielding more [`Read::Part`].
+    pub fn set_max_part_size(&mut self, max_part_size: usize) {
+        self.max_part_size = Some(max_part_size);
+    }
+
+    /// Limit the size of the body of each field, meaning each part
+    /// without a `filename`, to `max_field_size` bytes.
+    ///
+    /// This takes precedence over [`FormData::set_max_part_size`] for fields.
+    /// Fields with a bigger body make [`FormData::read`] return [`Error::PartTooLarge`].
+    pub fn set_max_field_size(&mut self, max_field_size: usize) {
+        self.max_field_size = Some(max_field_size);
+    }
+
+    /// Limit the size of the body of each file, meaning each part
+    /// with a `filename`, to `max_file_size` bytes.
+    ///
+    /// This takes precedence over [`FormData::set_max_part_size`] for files.
+    /// Files with a bigger body make [`FormData::read`] return [`Error::PartTooLarge`].
+    pub fn set_max_file_size(&mut self, max_file_size: usize) {
+        self.max_file_size = Some(max_file_size);
+    }
+
+    /// Limit the size of the whole multipart stream to `max_total_size` bytes.
+    ///
+    /// Every consumed byte counts towards the limit, including boundaries and headers.
+    /// So do the bytes buffered while looking for the first boundary or for the end
+    /// of the headers of a part, which otherwise could grow without bound.
+    /// Bigger streams make [`FormData::read`] return [`Error::TotalSizeExceeded`].
+    pub fn set_max_total_size(&mut self, max_total_size: usize) {
+        self.max_total_size = Some(max_total_size);
+    }
+
+    /// Refuse writes once `max_buffered_len` bytes are buffered.
+    ///
+    /// By default [`FormData::write`] queues every write, no matter how many bytes
+    /// haven't been read yet. With this set, writes made while [`FormData::buffered_len`]
+    /// is at least `max_buffered_len` are refused with [`WriteErrorKind::Full`], until
+    /// enough bytes are read. Writes following a [`Read::NeedsWrite`] are always
+    /// accepted, as the decoder can't make progress without them.
+    pub fn set_max_buffered_len(&mut self, max_buffered_len: usize) {
+        self.max_buffered_len = Some(max_buffered_len);
+    }
+
+    /// Limit the number of parts in the multipart stream to `max_parts`.
+    ///
+    /// Streams with more parts make [`FormData::read`] return
+    /// [`Error::TooManyParts`] instead of yielding more [`Read::NewPart`].
+    pub fn set_max_parts(&mut self, max_parts: usize) {
+        self.max_parts = Some(max_parts);
+    }
+
+    /// Limit the size of the headers of each part to `max_header_size` bytes,
+    /// including the empty line which terminates them.
+    ///
+    /// Parts with bigger headers make [`FormData::read`] return
+    /// [`Error::HeadersTooLarge`], instead of buffering more data.
+    pub fn set_max_header_size(&mut self, max_header_size: usize) {
+        self.max_header_size = Some(max_header_size);
+    }
+
+    /// Limit the length of the name of each header to `max_header_name_size` bytes,
+    /// and the length of its value to `max_header_value_size` bytes.
+    ///
+    /// Parts with longer headers make [`FormData::read`] return [`Error::HeaderFieldTooLarge`].
+    /// Both limits default to [`DEFAULT_MAX_HEADER_FIELD_SIZE`].
+    pub fn set_max_header_field_size(
+        &mut self,
+        max_header_name_size: usize,
+        max_header_value_size: usize,
+    ) {
+        self.max_header_name_size = max_header_name_size;
+        self.max_header_value_size = max_header_value_size;
+    }
+
+    /// Accept bare `\n` line endings in addition to `\r\n`.
+    ///
+    /// Some clients terminate boundary lines with `\n` alone, which isn't
+    /// allowed by RFC 2046. This is off by default.
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Match the boundary ignoring ASCII case.
+    ///
+    /// RFC 2046 boundaries are case sensitive, but some clients change the
+    /// case of the boundary in the body. This is off by default.
+    pub fn set_boundary_ignore_ascii_case(&mut self, boundary_ignore_ascii_case: bool) {
+        self.boundary_ignore_ascii_case = boundary_ignore_ascii_case;
+    }
+
+    /// Unfold header values continuing on the next line, which start with
+    /// a space or a tab.
+    ///
+    /// This line folding has been deprecated by RFC 7230, but some old
+    /// clients still send it. This is off by default, in which case
+    /// folded headers make [`FormData::read`] return [`Error::Headers`].
+    /// Unfolded headers are copied.
+    pub fn set_unfold_headers(&mut self, unfold_headers: bool) {
+        self.unfold_headers = unfold_headers;
+    }
+
+    /// Strip the UTF-8 byte order mark some clients put at the start of text fields.
+    ///
+    /// This is off by default. In order not to corrupt binary uploads, it only
+    /// applies to parts with a `text/*` `Content-Type`, or to parts with neither
+    /// a `filename` nor a `Content-Type`.
+    pub fn set_strip_bom(&mut self, strip_bom: bool) {
+        self.strip_bom = strip_bom;
+    }
+
+    /// Accept and discard bytes written after the closing boundary.
+    ///
+    /// RFC 2046 allows an epilogue after the closing boundary. This is
+    /// off by default, in which case [`FormData::write`] returns a [`WriteError`]
+    /// once the end of the multipart stream has been reached.
+    pub fn set_discard_epilogue(&mut self, discard_epilogue: bool) {
+        self.discard_epilogue = discard_epilogue;
+    }
+
+    /// Reject multipart streams which end without the closing boundary.
+    ///
+    /// When this is on, [`FormData::read`] returns [`Error::UnexpectedEof`]
+    /// instead of yielding what was left as the end of the last part.
+    /// This is off by default. See [`FormData::was_truncated`].
+    pub fn set_require_closing_boundary(&mut self, require_closing_boundary: bool) {
+        self.require_closing_boundary = require_closing_boundary;
+    }
+
+    /// Treat boundaries followed by an unexpected suffix as part of the body.
+    ///
+    /// A boundary inside the body of a part must be followed by a line break,
+    /// by `--` or by transport padding, otherwise [`FormData::read`] returns
+    /// [`Error::UnexpectedBoundarySuffix`]. When this is on, such a boundary
+    /// is instead yielded as [`Read::Part`] and the search for the real one goes on.
+    /// Such a boundary found before the first part is skipped as part of the preamble.
+    /// This is off by default.
+    pub fn set_allow_false_boundaries(&mut self, allow_false_boundaries: bool) {
+        self.allow_false_boundaries = allow_false_boundaries;
+    }
+
+    /// Collect the headers of each part into the [`RawHeaders`] yielded by [`Read::NewPart`].
+    ///
+    /// This is on by default. Turning it off saves an allocation per part
+    /// for callers which never look at the headers, in which case [`RawHeaders`]
+    /// doesn't contain any headers, and only [`RawHeaders::as_bytes`] is available.
+    /// Options relying on the headers, like [`FormData::set_strip_bom`],
+    /// don't have any effect.
+    pub fn set_collect_headers(&mut self, collect_headers: bool) {
+        self.collect_headers = collect_headers;
+    }
+
+    /// Give the headers of a part back, so that their allocation gets reused
+    /// for the headers of one of the following parts.
+    ///
+    /// Without this a new `Vec` is allocated for the headers of every part.
+    /// Only the largest allocation given back is kept.
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use bytes::Bytes;
+    /// use multiparty::server::sans_io::{FormData, Read};
+    ///
+    /// let mut form_data = FormData::new("boundary");
+    /// form_data.write(Bytes::from_static(
+    ///     b"--boundary\r\n\r\nfoo\r\n--boundary\r\n\r\nbar\r\n--boundary--\r\n",
+    /// ))?;
+    /// form_data.write_eof();
+    ///
+    /// loop {
+    ///     match form_data.read()? {
+    ///         Read::NewPart { headers, .. } => form_data.recycle_headers(headers),
+    ///         Read::Part(_) | Read::PartEof | Read::None => {}
+    ///         Read::Eof => break,
+    ///         Read::NeedsWrite => unreachable!(),
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn recycle_headers(&mut self, headers: RawHeaders) {
+        let headers = headers.into_headers();
+        if headers.capacity() > self.headers_buf.capacity() {
+            self.headers_buf = headers;
+        }
+    }
+
+    /// Check that the body of parts with a `Content-Length` header is as long as declared.
+    ///
+    /// Parts with a different length make [`FormData::read`] return
+    /// [`Error::PartLengthMismatch`]. Longer parts are rejected as soon as
+    /// they exceed the declared length. This is off by default.
+    pub fn set_validate_content_length(&mut self, validate_content_length: bool) {
+        self.validate_content_length = validate_content_length;
+    }
+
+    /// Decode the body of parts according to their `Content-Transfer-Encoding` header.
+    ///
+    /// `base64` encoded bodies are decoded before being yielded as [`Read::Part`].
+    /// `7bit`, `8bit` and `binary` bodies are yielded as is. Any other encoding
+    /// makes [`FormData::read`] return [`Error::UnsupportedTransferEncoding`].
+    /// This is off by default.
+    ///
+    /// The size limits, like [`FormData::set_max_part_size`], and the `Content-Length`
+    /// checked by [`FormData::set_validate_content_length`] apply to the body as it
+    /// was sent, before being decoded, while [`FormData::current_part_len`] counts
+    /// the decoded bytes.
+    #[cfg(feature = "cte")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cte")))]
+    pub fn set_decode_transfer_encoding(&mut self, decode_transfer_encoding: bool) {
+        self.decode_transfer_encoding = decode_transfer_encoding;
+    }
+
+    /// Call `on_event` from [`FormData::read`] every time a part starts,
+    /// yields some bytes or ends, and once when the multipart stream ends.
+    ///
+    /// Useful for collecting metrics without wrapping the decoder.
+    pub fn set_on_event<F>(&mut self, on_event: F)
+    where
+        F: FnMut(Event<'_>) + Send + Sync + 'static,
+    {
+        self.on_event = Some(Box::new(on_event));
+    }
+
+    /// Reset this `FormData`, so that it can decode a new multipart stream.
+    ///
+    /// The boundary and the configured limits are kept.
+    pub fn reset(&mut self) {
+        self.bytes1 = Bytes::new();
+        self.bytes2 = Bytes::new();
+        self.queue.clear();
+        self.eof = false;
+        self.needs_write = false;
+        self.eof_event_emitted = false;
+        self.truncated = false;
+        #[cfg(feature = "cte")]
+        {
+            self.base64 = None;
+        }
+        self.strip_bom_pending = false;
+        self.headers_scanned = 0;
+        self.saw_boundary = false;
+        self.carriage_return_stripped = false;
+        self.parts = 0;
+        self.part_limit = None;
+        self.part_content_length = None;
+        self.part_len = 0;
+        self.current_part_len = 0;
+        self.consumed = 0;
+        self.state = State::Uninit;
+    }
+
+    /// Set the boundary of a `FormData` which hasn't been given any bytes yet,
+    /// like the ones created through [`FormData::pending`].
+    ///
+    /// Returns a [`SetBoundaryError`] if bytes have already been written or read,
+    /// in which case [`FormData::reset_with_boundary`] has to be used instead.
+    pub fn set_boundary(&mut self, boundary: &str) -> Result<(), SetBoundaryError> {
+        if self.state != State::Uninit || self.consumed != 0 || !self.bytes1.is_empty() {
+            return Err(SetBoundaryError(()));
+        }
+
+        self.boundary = Boundary::new(boundary);
+        Ok(())
+    }
+
+    /// Reset this `FormData`, so that it can decode a new multipart stream
+    /// delimited by `boundary`.
+    ///
+    /// The configured limits are kept.
+    pub fn reset_with_boundary(&mut self, boundary: &str) {
+        self.boundary = Boundary::new(boundary);
+        self.reset();
+    }
+
     /// Add more [`Bytes`] to the internal state.
     ///
     /// In order to achieve 0 copy decoding `bytes` should have a
     /// length `>= boundary.len() + 4`. Smaller `bytes` are still
     /// accepted, but might require the decoder to do more work.
     ///
-    /// Returns `Err(bytes)` if this `FormData` isn't expecting
-    /// more bytes.
-    pub fn write(&mut self, bytes: Bytes) -> Result<(), Bytes> {
-        if matches!(self.state, State::WriteEof | State::Eof) {
-            // It doesn't make sense to write after reaching eof
-            Err(bytes)
-        } else if self.bytes1.is_empty() {
-            self.bytes1 = bytes;
+    /// `bytes` can be written even if [`Read::NeedsWrite`] wasn't
+    /// returned, in which case they're queued until they're needed.
+    /// The queue is unbounded unless [`FormData::set_max_buffered_len`]
+    /// is used, so by default writes are never refused for lack of space.
+    ///
+    /// Returns a [`WriteError`] holding `bytes` if this `FormData` isn't
+    /// expecting more bytes, either because [`FormData::write_eof`] has
+    /// already been called or because the multipart stream has ended,
+    /// or if [`FormData::set_max_buffered_len`] bytes are already buffered.
+    pub fn write(&mut self, bytes: Bytes) -> Result<(), WriteError> {
+        if self.eof {
+            Err(WriteError {
+                kind: WriteErrorKind::EofWritten,
+                bytes,
+            })
+        } else if self.state == State::Eof && self.discard_epilogue {
+            // Ignore the epilogue
             Ok(())
-        } else if self.bytes2.is_empty() {
-            self.bytes2 = bytes;
+        } else if self.state == State::Eof {
+            // It doesn't make sense to write after reaching eof
+            Err(WriteError {
+                kind: WriteErrorKind::Finished,
+                bytes,
+            })
+        } else if bytes.is_empty() {
+            // Nothing to do
             Ok(())
+        } else if self.is_full() {
+            Err(WriteError {
+                kind: WriteErrorKind::Full,
+                bytes,
+            })
         } else {
-            // No space to put `bytes`
-            Err(bytes)
+            self.needs_write = false;
+            if self.bytes1.is_empty() {
+                self.bytes1 = bytes;
+            } else if self.bytes2.is_empty() {
+                self.bytes2 = bytes;
+            } else {
+                self.queue.push_back(bytes);
+            }
+            Ok(())
         }
     }
 
+    /// Copy `slice` into the internal state.
+    ///
+    /// Unlike [`FormData::write`] this always copies, by design, for callers
+    /// reading into a reusable buffer which can't cheaply produce [`Bytes`].
+    /// `slice` is copied into the scratch space of this `FormData`, which
+    /// gets reused once the [`Bytes`] previously read from it are dropped.
+    /// See [`FormData::with_capacity`].
+    ///
+    /// Returns a [`WriteError`], holding a copy of `slice`, in the same
+    /// cases as [`FormData::write`].
+    pub fn write_slice(&mut self, slice: &[u8]) -> Result<(), WriteError> {
+        self.scratch.extend_from_slice(slice);
+        let bytes = self.scratch.split().freeze();
+        self.write(bytes)
+    }
+
     /// Signal to [`FormData`] that no more calls to [`FormData::write`] are
     /// going to be made, as EOF for the multipart bytes stream has been reached.
     pub fn write_eof(&mut self) {
-        self.state = if self.state == State::Part {
-            State::WriteEof
-        } else {
-            State::Eof
+        self.eof = true;
+    }
+
+    /// Write `bytes` and read everything that can be decoded from them.
+    ///
+    /// The returned iterator yields every [`Read`] until [`Read::NeedsWrite`]
+    /// would be returned, skipping [`Read::None`]. It ends after yielding
+    /// [`Read::Eof`] or an error.
+    ///
+    /// Returns a [`WriteError`] if `bytes` couldn't be written, like when they
+    /// come after the end of the multipart stream. See [`FormData::write`].
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use bytes::Bytes;
+    /// use multiparty::server::sans_io::{FormData, Read};
+    ///
+    /// let mut form_data = FormData::new("abcd");
+    /// let reads = form_data
+    ///     .write_and_read(Bytes::from_static(
+    ///         b"--abcd\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n--abcd--\r\n",
+    ///     ))?
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// assert!(matches!(reads[0], Read::NewPart { .. }));
+    /// assert!(matches!(&reads[1], Read::Part(bytes) if bytes == "bar"));
+    /// assert!(matches!(reads[2], Read::PartEof));
+    /// assert!(matches!(reads[3], Read::Eof));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_and_read(
+        &mut self,
+        bytes: Bytes,
+    ) -> Result<impl Iterator<Item = Result<Read, Error>> + '_, WriteError> {
+        self.write(bytes)?;
+
+        let mut done = false;
+        Ok(iter::from_fn(move || {
+            if done {
+                return None;
+            }
+
+            loop {
+                match self.read() {
+                    Ok(Read::None) => {}
+                    Ok(Read::NeedsWrite) => {
+                        done = true;
+                        return None;
+                    }
+                    read => {
+                        done = matches!(read, Ok(Read::Eof) | Err(_));
+                        return Some(read);
+                    }
+                }
+            }
+        }))
+    }
+
+    /// The `Content-Length` declared by the current part, if it's within the
+    /// size limit of the part.
+    ///
+    /// Useful for pre-sizing the buffer the body is going to be read into.
+    /// Returns `None` if there's no size limit, as the header can't be trusted.
+    pub fn content_length_hint(&self) -> Option<usize> {
+        match (self.part_content_length, self.part_limit) {
+            (Some(len), Some(limit)) if len <= limit as u64 => Some(len as usize),
+            _ => None,
+        }
+    }
+
+    /// The `Content-Length` declared by the current part, if it's being validated.
+    #[cfg(feature = "futures03")]
+    pub(crate) fn validated_content_length(&self) -> Option<u64> {
+        self.part_content_length
+            .filter(|_| self.validate_content_length)
+    }
+
+    /// The boundary delimiting the parts, as it was passed to the constructor.
+    pub fn boundary(&self) -> &str {
+        self.boundary.as_str()
+    }
+
+    /// Whether the first boundary of the multipart stream has been found.
+    ///
+    /// Once [`Read::Eof`] has been returned, this tells a well-formed
+    /// multipart stream without any parts, made of just the closing boundary,
+    /// apart from an empty body or one without any boundary.
+    pub fn saw_any_boundary(&self) -> bool {
+        self.saw_boundary
+    }
+
+    /// Whether the multipart stream ended without the closing boundary.
+    ///
+    /// When this happens in the middle of a part, whatever was left
+    /// is still yielded as the body of the last part, followed by
+    /// [`Read::Eof`]. This method can be called after [`Read::Eof`]
+    /// to tell truncated streams apart from complete ones.
+    pub fn was_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// The number of bytes written to this `FormData` which
+    /// haven't been consumed yet.
+    ///
+    /// This includes the bytes queued by [`FormData::write`].
+    pub fn buffered_len(&self) -> usize {
+        self.bytes1.len() + self.bytes2.len() + self.queue.iter().map(Bytes::len).sum::<usize>()
+    }
+
+    /// Whether a call to [`FormData::write`] would succeed.
+    ///
+    /// Writes are queued when [`Read::NeedsWrite`] hasn't been returned, so
+    /// unless [`FormData::set_max_buffered_len`] is used this only returns
+    /// `false` once [`FormData::write_eof`] has been called or the end of
+    /// the multipart stream has been reached.
+    pub fn has_capacity(&self) -> bool {
+        !self.eof && self.state != State::Eof && !self.is_full()
+    }
+
+    fn is_full(&self) -> bool {
+        match self.max_buffered_len {
+            Some(limit) => !self.needs_write && self.buffered_len() >= limit,
+            None => false,
         }
     }
 
@@ -143,15 +1068,149 @@ impl FormData {
         self.state == State::Eof
     }
 
+    /// The number of bytes of the body of the current part yielded so far
+    /// through [`Read::Part`].
+    ///
+    /// Resets to `0` when [`Read::NewPart`] or [`Read::PartEof`] is yielded.
+    pub fn current_part_len(&self) -> u64 {
+        self.current_part_len
+    }
+
+    /// Look at the kind of [`Read`] the next call to [`FormData::read`]
+    /// is going to yield, without advancing the decoder.
+    ///
+    /// Returns `None` if it can't be told without doing the work
+    /// [`FormData::read`] would do. The next call to [`FormData::read`]
+    /// may still return an error, for example if a limit is exceeded.
+    pub fn peek(&self) -> Option<ReadKind> {
+        if self.state == State::Eof {
+            return Some(ReadKind::Eof);
+        }
+
+        if self.bytes1.is_empty() {
+            // What `needs_write!` in `FormData::read` does
+            return match self.state {
+                _ if !self.eof => Some(ReadKind::NeedsWrite),
+                State::Uninit => Some(ReadKind::Eof),
+                // An error is returned
+                _ if self.require_closing_boundary => None,
+                State::Part | State::WriteEof => Some(ReadKind::PartEof),
+                _ => Some(ReadKind::Eof),
+            };
+        }
+
+        if self.strip_bom_pending {
+            return None;
+        }
+
+        match self.state {
+            State::BoundarySuffix if self.bytes1.starts_with(b"--") => Some(ReadKind::Eof),
+            State::Headers
+                if self.bytes1.starts_with(b"\r\n")
+                    || find_bytes(&self.bytes1, b"\r\n\r\n").is_some() =>
+            {
+                Some(ReadKind::NewPart)
+            }
+            State::Part => {
+                #[cfg(feature = "cte")]
+                {
+                    if self.base64.is_some() {
+                        // The decoder might need more bytes before yielding something
+                        return None;
+                    }
+                }
+
+                let boundary = self.part_boundary();
+                let hold_back = boundary.len() - 1 + usize::from(self.lenient);
+
+                let carriage_return = self.lenient && self.bytes1.starts_with(b"\r");
+                if self.bytes1.len() <= hold_back {
+                    // `bytes2` may be needed to find the boundary
+                    None
+                } else if self.starts_with_boundary(&self.bytes1, boundary)
+                    || (carriage_return && self.starts_with_boundary(&self.bytes1[1..], boundary))
+                {
+                    Some(ReadKind::PartEof)
+                } else {
+                    Some(ReadKind::Part)
+                }
+            }
+            _ => None,
+        }
+    }
+
     /// Get a new item of multipart data.
     pub fn read(&mut self) -> Result<Read, Error> {
+        let read = self.read_inner();
+        // Checked before anything else, so that the events only see what's actually returned
+        let read = match self.max_total_size {
+            Some(limit) if self.consumed > limit as u64 => Err(Error::TotalSizeExceeded { limit }),
+            // Nothing gets consumed until the end of the headers is found,
+            // so count what's waiting for it too
+            Some(limit)
+                if matches!(read, Ok(Read::NeedsWrite))
+                    && matches!(
+                        self.state,
+                        State::Uninit | State::BoundarySuffix | State::Headers
+                    )
+                    && self.consumed + self.buffered_len() as u64 > limit as u64 =>
+            {
+                Err(Error::TotalSizeExceeded { limit })
+            }
+            _ => read,
+        };
+
+        match &read {
+            Ok(Read::NeedsWrite) => self.needs_write = true,
+            Ok(Read::Part(bytes)) => self.current_part_len += bytes.len() as u64,
+            Ok(Read::NewPart { .. }) | Ok(Read::PartEof) => self.current_part_len = 0,
+            _ => {}
+        }
+
+        if let Some(on_event) = &mut self.on_event {
+            match &read {
+                Ok(Read::NewPart { headers, index }) => on_event(Event::NewPart {
+                    headers,
+                    index: *index,
+                }),
+                Ok(Read::Part(bytes)) => on_event(Event::Part { len: bytes.len() }),
+                Ok(Read::PartEof) => on_event(Event::PartEof),
+                Ok(Read::Eof) if !self.eof_event_emitted => {
+                    self.eof_event_emitted = true;
+                    on_event(Event::Eof);
+                }
+                _ => {}
+            }
+        }
+
+        read
+    }
+
+    fn read_inner(&mut self) -> Result<Read, Error> {
         macro_rules! needs_write {
             () => {
                 match self.state {
-                    State::WriteEof | State::Eof => {
+                    State::Uninit | State::Eof if self.eof => {
+                        self.state = State::Eof;
+                        Ok(Read::Eof)
+                    }
+                    _ if self.eof && self.require_closing_boundary => {
+                        self.truncated = true;
+                        self.state = State::Eof;
+                        Err(Error::UnexpectedEof)
+                    }
+                    State::Part | State::WriteEof if self.eof => {
+                        // End the truncated part before ending the stream
+                        self.truncated = true;
+                        self.state = State::Eof;
+                        Ok(Read::PartEof)
+                    }
+                    _ if self.eof => {
+                        self.truncated = true;
                         self.state = State::Eof;
                         Ok(Read::Eof)
                     }
+                    State::Eof => Ok(Read::Eof),
                     _ => Ok(Read::NeedsWrite),
                 }
             };
@@ -160,16 +1219,22 @@ impl FormData {
         macro_rules! needs_write_while_parsing {
             () => {
                 match self.state {
-                    State::WriteEof | State::Eof => {
+                    _ if self.eof => {
+                        self.truncated = true;
                         self.state = State::Eof;
                         Err(Error::UnexpectedEof)
                     }
+                    State::Eof => Ok(Read::Eof),
                     _ => Ok(Read::NeedsWrite),
                 }
             };
         }
 
-        if self.bytes1.is_empty() {
+        if self.boundary.as_str().is_empty() {
+            return Err(Error::EmptyBoundary);
+        }
+
+        if self.bytes1.is_empty() {
             debug_assert!(self.bytes2.is_empty());
 
             return needs_write!();
@@ -177,27 +1242,95 @@ impl FormData {
 
         match self.state {
             State::Uninit => {
-                let boundary = self.boundary.with_dashes();
+                // RFC 2046 only allows the first boundary at the start of
+                // the stream or right after the line break ending the preamble
+                if self.consumed == 0 {
+                    let boundary = self.boundary.with_dashes();
+                    let buffered_len = self.buffered_len();
+
+                    if buffered_len < boundary.len()
+                        && !self.eof
+                        && self.buffered_starts_with_boundary(&boundary[..buffered_len])
+                    {
+                        return needs_write!();
+                    }
+                    if self.buffered_starts_with_boundary(&boundary) {
+                        if self.allow_false_boundaries {
+                            match self.has_boundary_suffix_after(boundary.len()) {
+                                Some(true) => {}
+                                Some(false) => {
+                                    // Part of the preamble
+                                    self.skip(boundary.len());
+                                    return Ok(Read::None);
+                                }
+                                None if self.eof => {}
+                                None => return needs_write!(),
+                            }
+                        }
+
+                        self.skip(boundary.len());
+                        self.saw_boundary = true;
+                        self.state = State::BoundarySuffix;
+                        return Ok(Read::None);
+                    }
+                }
 
-                match self.read_until_boundary(&boundary) {
+                match self.read_until_boundary() {
                     Some((bytes, true)) => {
                         drop(bytes);
 
-                        self.skip(boundary.len());
+                        if self.allow_false_boundaries {
+                            match self.has_boundary_suffix() {
+                                Some(true) => {}
+                                Some(false) => {
+                                    // Part of the preamble, keep looking
+                                    self.skip(self.part_boundary().len());
+                                    self.carriage_return_stripped = false;
+                                    return Ok(Read::None);
+                                }
+                                None if self.eof => {}
+                                None => return needs_write!(),
+                            }
+                        }
+
+                        self.skip(self.part_boundary().len());
+                        self.carriage_return_stripped = false;
+                        self.saw_boundary = true;
                         self.state = State::BoundarySuffix;
                         Ok(Read::None)
                     }
-                    Some((_, false)) | None => {
+                    Some((_, false)) => {
+                        // Skipped part of the preamble
+                        Ok(Read::None)
+                    }
+                    None => {
                         needs_write!()
                     }
                 }
             }
             State::BoundarySuffix => {
+                // Skip the transport padding allowed by RFC 2046
+                let padding = self
+                    .bytes1
+                    .iter()
+                    .take_while(|&&b| b == b' ' || b == b'\t')
+                    .count();
+                if padding > 0 {
+                    self.skip(padding);
+                    return Ok(Read::None);
+                }
+
                 if starts_with_between(&self.bytes1, &self.bytes2, b"\r\n") {
                     // There's another part after this one
                     self.skip(2);
                     self.state = State::Headers;
 
+                    Ok(Read::None)
+                } else if self.lenient && starts_with_between(&self.bytes1, &self.bytes2, b"\n") {
+                    // There's another part after this one, but it uses bare line feeds
+                    self.skip(1);
+                    self.state = State::Headers;
+
                     Ok(Read::None)
                 } else if starts_with_between(&self.bytes1, &self.bytes2, b"--") {
                     // There are no more parts
@@ -206,66 +1339,139 @@ impl FormData {
                 } else if self.bytes1.len() + self.bytes2.len() < 2 {
                     needs_write_while_parsing!()
                 } else {
-                    Err(Error::UnexpectedBoundarySuffix)
+                    Err(Error::UnexpectedBoundarySuffix {
+                        offset: self.consumed,
+                    })
                 }
             }
             State::Headers => {
-                let mut headers = [httparse::EMPTY_HEADER; 8];
-
-                match httparse::parse_headers(&self.bytes1, &mut headers) {
-                    Ok(httparse::Status::Complete((read, headers))) => {
-                        let headers = headers
-                            .iter()
-                            .map(|header| {
-                                let name = self.bytes1.slice_ref(header.name.as_bytes());
-                                let value = self.bytes1.slice_ref(header.value);
-                                (name, value)
-                            })
-                            .collect::<Vec<_>>();
+                // `httparse` can't resume parsing, so only call it once the empty line ending
+                // the headers has been buffered, instead of rescanning them after every write
+                if !self.eof && !self.find_headers_end() {
+                    return match self.max_header_size {
+                        Some(limit) if self.buffered_len() > limit => {
+                            Err(Error::HeadersTooLarge { limit })
+                        }
+                        _ => needs_write!(),
+                    };
+                }
+
+                // Headers split across multiple `Bytes` are copied into a single one for `httparse`,
+                // while the original `Bytes` are kept, so that the body isn't copied too
+                let mut headers_buf = mem::take(&mut self.headers_buf);
+                let mut parsed = self.parse_headers(&self.bytes1, &mut headers_buf);
+                // `httparse` can also fail on headers which are only invalid until the rest is seen
+                if !matches!(parsed, Ok(Some(_))) && !self.bytes2.is_empty() {
+                    let joined = self.join_buffered();
+                    parsed = self.parse_headers(&joined, &mut headers_buf);
+                }
+                self.headers_buf = headers_buf;
+
+                match parsed? {
+                    Some((read, headers)) => {
+                        if let Some(limit) = self.max_parts {
+                            if self.parts >= limit {
+                                return Err(Error::TooManyParts { limit });
+                            }
+                        }
+
+                        #[cfg(feature = "cte")]
+                        self.start_transfer_encoding(&headers)?;
 
                         self.skip(read);
+                        self.headers_scanned = 0;
+                        self.strip_bom_pending = self.strip_bom && is_text(&headers);
+                        self.part_limit = self.part_limit(&headers);
+                        self.part_content_length = headers.content_length().ok().flatten();
+                        let index = self.parts;
+                        self.parts += 1;
+                        self.part_len = 0;
                         self.state = State::Part;
 
-                        let headers = RawHeaders::new(headers);
-                        Ok(Read::NewPart { headers })
-                    }
-                    Ok(httparse::Status::Partial) => {
-                        self.set_need_bytes2();
-                        needs_write_while_parsing!()
+                        Ok(Read::NewPart { headers, index })
                     }
-                    Err(err) => Err(Error::Headers(err)),
+                    None => needs_write_while_parsing!(),
                 }
             }
             State::Part => {
-                let boundary = self.boundary.with_new_line_and_dashes();
+                if self.strip_bom_pending {
+                    if self.buffered_len() < BOM.len() && !self.eof {
+                        return needs_write!();
+                    }
+
+                    if starts_with_chain(self.buffered(), BOM) {
+                        self.skip(BOM.len());
+                    }
+                    self.strip_bom_pending = false;
+                    return Ok(Read::None);
+                }
 
-                match self.read_until_boundary(&boundary) {
+                match self.read_until_boundary() {
                     Some((bytes, true)) => {
                         if bytes.is_empty() {
-                            self.skip(boundary.len());
+                            if self.allow_false_boundaries {
+                                match self.has_boundary_suffix() {
+                                    Some(true) => {}
+                                    Some(false) => {
+                                        let bytes = self.take_false_boundary();
+                                        return self.read_part(bytes);
+                                    }
+                                    None if self.eof => {}
+                                    None => return needs_write!(),
+                                }
+                            }
+
+                            #[cfg(feature = "cte")]
+                            {
+                                if let Some(bytes) = self.finish_transfer_encoding()? {
+                                    return Ok(Read::Part(bytes));
+                                }
+                            }
+
+                            if let Some(expected) = self.part_content_length {
+                                let actual = self.part_len as u64;
+                                if self.validate_content_length && actual != expected {
+                                    return Err(Error::PartLengthMismatch { expected, actual });
+                                }
+                            }
+
+                            self.skip(self.part_boundary().len());
+                            self.carriage_return_stripped = false;
+                            self.part_len = 0;
                             self.state = State::BoundarySuffix;
                             Ok(Read::PartEof)
                         } else {
-                            Ok(Read::Part(bytes))
+                            self.read_part(bytes)
                         }
                     }
-                    Some((bytes, false)) => Ok(Read::Part(bytes)),
+                    Some((bytes, false)) => self.read_part(bytes),
+                    None if self.eof && self.require_closing_boundary => {
+                        self.truncated = true;
+                        self.state = State::Eof;
+                        Err(Error::UnexpectedEof)
+                    }
+                    None if self.eof => {
+                        // Whatever is left is the end of the current part
+                        self.state = State::WriteEof;
+                        Ok(Read::None)
+                    }
                     None => {
                         needs_write!()
                     }
                 }
             }
             State::WriteEof => {
-                let boundary = self.boundary.with_new_line_and_dashes();
-
-                match self.read_until_boundary(&boundary) {
-                    Some((bytes, _)) if !bytes.is_empty() => Ok(Read::Part(bytes)),
+                match self.read_until_boundary() {
+                    Some((bytes, _)) if !bytes.is_empty() => self.read_part(bytes),
                     _ => {
-                        let bytes =
-                            join_bytes(mem::take(&mut self.bytes1), mem::take(&mut self.bytes2));
+                        let bytes = self.join_buffered();
+                        self.bytes1 = Bytes::new();
+                        self.bytes2 = Bytes::new();
+                        self.queue.clear();
+                        self.consumed += bytes.len() as u64;
 
-                        self.state = State::Eof;
-                        Ok(Read::Part(bytes))
+                        // The part is ended by the next call
+                        self.read_part(bytes)
                     }
                 }
             }
@@ -273,84 +1479,2561 @@ impl FormData {
         }
     }
 
-    /// Read bytes from the internal state.
+    /// The boundary which terminates the body of a part.
+    ///
+    /// In lenient mode this doesn't include the `\r`, which gets
+    /// stripped by [`FormData::read_until_boundary`] if present.
+    fn part_boundary(&self) -> &[u8] {
+        if self.lenient {
+            self.boundary.as_line_feed_and_dashes()
+        } else {
+            self.boundary.as_new_line_and_dashes()
+        }
+    }
+
+    /// The limit on the size of the body of the part with `headers`.
+    fn part_limit(&self, headers: &RawHeaders) -> Option<usize> {
+        if self.max_field_size.is_none() && self.max_file_size.is_none() {
+            return self.max_part_size;
+        }
+
+        let limit = match headers.filename() {
+            Ok(Some(_)) => self.max_file_size,
+            _ => self.max_field_size,
+        };
+        limit.or(self.max_part_size)
+    }
+
+    /// Yield `bytes` from the current part, enforcing its size limit.
+    fn read_part(&mut self, bytes: Bytes) -> Result<Read, Error> {
+        self.part_len += bytes.len();
+
+        match self.part_limit {
+            Some(limit) if self.part_len > limit => return Err(Error::PartTooLarge { limit }),
+            _ => {}
+        }
+        match self.part_content_length {
+            Some(expected) if self.validate_content_length && self.part_len as u64 > expected => {
+                let actual = self.part_len as u64;
+                return Err(Error::PartLengthMismatch { expected, actual });
+            }
+            _ => {}
+        }
+
+        #[cfg(feature = "cte")]
+        if let Some(base64) = &mut self.base64 {
+            let bytes = base64.decode(&bytes).ok_or(Error::InvalidBase64)?;
+            return Ok(if bytes.is_empty() {
+                Read::None
+            } else {
+                Read::Part(bytes)
+            });
+        }
+
+        Ok(Read::Part(bytes))
+    }
+
+    /// Prepare to decode the body of the part with `headers`.
+    #[cfg(feature = "cte")]
+    fn start_transfer_encoding(&mut self, headers: &RawHeaders) -> Result<(), Error> {
+        self.base64 = None;
+        if !self.decode_transfer_encoding {
+            return Ok(());
+        }
+
+        let encoding = match headers.get("content-transfer-encoding") {
+            Some(encoding) => str::from_utf8(encoding)
+                .map_err(|_| Error::UnsupportedTransferEncoding)?
+                .trim(),
+            None => return Ok(()),
+        };
+        if encoding.eq_ignore_ascii_case("base64") {
+            self.base64 = Some(Base64Decoder::default());
+            Ok(())
+        } else if encoding.eq_ignore_ascii_case("7bit")
+            || encoding.eq_ignore_ascii_case("8bit")
+            || encoding.eq_ignore_ascii_case("binary")
+        {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedTransferEncoding)
+        }
+    }
+
+    /// Decode what's left of the body of the current part.
+    ///
+    /// Returns `Some(bytes)` the first time it's called with leftover bytes.
+    #[cfg(feature = "cte")]
+    fn finish_transfer_encoding(&mut self) -> Result<Option<Bytes>, Error> {
+        match self.base64.take() {
+            Some(mut base64) => {
+                let bytes = base64.finish().ok_or(Error::InvalidBase64)?;
+                Ok(Some(bytes).filter(|bytes| !bytes.is_empty()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Read bytes from the internal state, until the [`FormData::part_boundary`].
     /// Returns:
     ///
     /// * `Some((Bytes, true))` if the `boundary` has been found.
     ///   `Bytes` contain bytes until the start of the `boundary`.
     /// * `Some((Bytes, false))` if the `boundary` hasn't been found.
     /// * `None` if more bytes are needed.
-    fn read_until_boundary(&mut self, boundary: &[u8]) -> Option<(Bytes, bool)> {
+    fn starts_with_boundary(&self, bytes: &[u8], boundary: &[u8]) -> bool {
+        if self.boundary_ignore_ascii_case {
+            bytes.len() >= boundary.len() && bytes[..boundary.len()].eq_ignore_ascii_case(boundary)
+        } else {
+            bytes.starts_with(boundary)
+        }
+    }
+
+    fn buffered_starts_with_boundary(&self, boundary: &[u8]) -> bool {
+        if self.boundary_ignore_ascii_case {
+            starts_with_chain_ignore_ascii_case(self.buffered(), boundary)
+        } else {
+            starts_with_chain(self.buffered(), boundary)
+        }
+    }
+
+    fn read_until_boundary(&mut self) -> Option<(Bytes, bool)> {
+        let read = self.read_until_boundary_inner();
+        if let Some((bytes, _)) = &read {
+            self.consumed += bytes.len() as u64;
+        }
+        read
+    }
+
+    fn read_until_boundary_inner(&mut self) -> Option<(Bytes, bool)> {
         debug_assert!(!self.bytes1.is_empty());
-        debug_assert!(!boundary.is_empty());
 
-        if self.bytes1.len() >= boundary.len() {
+        // Borrowed instead of cloned, as this runs for every `Read::Part`
+        let boundary = self.part_boundary();
+
+        // The number of bytes which can't be returned yet, as they could be the start of the `boundary`.
+        // In lenient mode this includes the `\r` which may come before the `boundary`.
+        let hold_back = boundary.len() - 1 + usize::from(self.lenient);
+
+        if self.bytes1.len() > hold_back {
             // `bytes1 >= boundary`, so we can use the normal algorithm for searching for the boundary
 
-            match find_bytes(&self.bytes1, boundary) {
+            let find = if self.boundary_ignore_ascii_case {
+                find_bytes_ignore_ascii_case
+            } else {
+                find_bytes
+            };
+            match find(&self.bytes1, boundary) {
                 Some(i) => {
                     // Boundary starts at `i`
-                    Some((self.bytes1.split_to(i), true))
+                    let bytes = self.bytes1.split_to(i);
+                    Some((self.strip_carriage_return(bytes), true))
                 }
                 None => {
-                    // No full boundary could be found. Return `self.bytes1` except for the last `boundary.len()) - 1` bytes
-                    let bytes = self.bytes1.split_to(self.bytes1.len() - boundary.len() + 1);
+                    // No full boundary could be found. Return `self.bytes1` except for the last `hold_back` bytes
+                    let bytes = self.bytes1.split_to(self.bytes1.len() - hold_back);
                     Some((bytes, false))
                 }
             }
         } else {
             // `bytes1 < boundary`, we have to get smart
 
-            let bytes12_len = self.bytes1.len() + self.bytes2.len();
-            if bytes12_len >= boundary.len() {
-                // `bytes1 + bytes2 >= boundary`
+            // Search across `bytes1`, `bytes2` and the queue instead of joining them,
+            // so that streams yielding tiny `Bytes` don't cause allocations
+            let buffered_len = self.buffered_len();
+            if buffered_len > hold_back {
+                // `bytes1 + bytes2 + queue >= boundary`
 
-                match find_bytes_split(&self.bytes1, &self.bytes2, boundary) {
+                let rest = self.buffered().skip(1);
+                let find = if self.boundary_ignore_ascii_case {
+                    find_bytes_chain_ignore_ascii_case
+                } else {
+                    find_bytes_chain
+                };
+                match find(&self.bytes1, rest, boundary) {
                     Some(i) => {
                         // Boundary starts at `i` inside `bytes1`
-                        Some((self.bytes1.split_to(i), true))
+                        let bytes = self.bytes1.split_to(i);
+                        Some((self.strip_carriage_return(bytes), true))
                     }
                     None => {
-                        // No boundary between `bytes1` and `bytes2`
+                        // No boundary starting inside `bytes1`
 
-                        // Skip at most `(self.bytes1.len() + self.bytes2.len()) - boundary.len() + 1`
-                        let to_skip = bytes12_len - boundary.len() + 1;
+                        // Skip at most `buffered_len - hold_back`
+                        let to_skip = buffered_len - hold_back;
                         let bytes = if to_skip < self.bytes1.len() {
                             self.bytes1.split_to(to_skip)
+                        } else if self.lenient && self.bytes1.ends_with(b"\r") {
+                            // The `boundary` could start at the beginning of `bytes2`,
+                            // making the `\r` part of it
+                            if self.bytes1.len() == 1 {
+                                self.set_need_bytes2();
+                                return self.read_until_boundary_inner();
+                            }
+
+                            self.bytes1.split_to(self.bytes1.len() - 1)
                         } else {
-                            mem::replace(&mut self.bytes1, mem::take(&mut self.bytes2))
+                            let bytes2 = self.take_bytes2();
+                            mem::replace(&mut self.bytes1, bytes2)
                         };
                         Some((bytes, false))
                     }
                 }
             } else {
-                // We need `bytes2`
-                self.set_need_bytes2();
+                // We need more bytes
                 None
             }
         }
     }
 
+    /// In lenient mode, remove the `\r` which was found right before the boundary.
+    fn strip_carriage_return(&mut self, mut bytes: Bytes) -> Bytes {
+        if self.lenient && bytes.ends_with(b"\r") {
+            bytes.truncate(bytes.len() - 1);
+            // The `\r` is part of the boundary
+            self.consumed += 1;
+            self.carriage_return_stripped = true;
+        }
+        bytes
+    }
+
+    /// Determine if the boundary at the start of the buffered bytes is followed
+    /// by a valid suffix, or `None` if more bytes are needed to tell.
+    fn has_boundary_suffix(&self) -> Option<bool> {
+        self.has_boundary_suffix_after(self.part_boundary().len())
+    }
+
+    /// Like [`FormData::has_boundary_suffix`], for a boundary `len` bytes long.
+    fn has_boundary_suffix_after(&self, len: usize) -> Option<bool> {
+        let mut suffix = self
+            .buffered()
+            .flat_map(|bytes| bytes.iter().copied())
+            .skip(len)
+            .skip_while(|&b| b == b' ' || b == b'\t');
+
+        match (suffix.next(), suffix.next()) {
+            (Some(b'\n'), _) if self.lenient => Some(true),
+            (Some(b'\r'), Some(b'\n')) | (Some(b'-'), Some(b'-')) => Some(true),
+            (None, _) | (Some(b'\r'), None) | (Some(b'-'), None) => None,
+            (Some(_), _) => Some(false),
+        }
+    }
+
+    /// Take the boundary at the start of the buffered bytes, which turned out to be part of the body.
+    fn take_false_boundary(&mut self) -> Bytes {
+        let len = self.part_boundary().len();
+        if mem::take(&mut self.carriage_return_stripped) {
+            self.scratch.put_u8(b'\r');
+        }
+
+        let mut remaining = len;
+        for bytes in iter::once(&self.bytes1)
+            .chain(iter::once(&self.bytes2))
+            .chain(self.queue.iter())
+        {
+            let n = remaining.min(bytes.len());
+            self.scratch.put_slice(&bytes[..n]);
+            remaining -= n;
+            if remaining == 0 {
+                break;
+            }
+        }
+
+        self.skip(len);
+        self.scratch.split().freeze()
+    }
+
     /// Skip `len` bytes from the internal [`Bytes`].
     fn skip(&mut self, len: usize) {
-        debug_assert!((self.bytes1.len() + self.bytes2.len()) >= len);
+        debug_assert!(self.buffered_len() >= len);
+        self.consumed += len as u64;
 
-        if self.bytes1.len() > len {
-            self.bytes1.advance(len);
-        } else {
-            let bytes1 = mem::replace(&mut self.bytes1, mem::take(&mut self.bytes2));
-            self.bytes1.advance(len - bytes1.len());
+        let mut len = len;
+        while !self.bytes1.is_empty() && len >= self.bytes1.len() {
+            len -= self.bytes1.len();
+            self.bytes1 = self.take_bytes2();
         }
+        self.bytes1.advance(len);
     }
 
     /// Prepare space in [`FormData`] for more [`Bytes`] to be written.
     fn set_need_bytes2(&mut self) {
-        self.bytes1 = join_bytes(mem::take(&mut self.bytes1), mem::take(&mut self.bytes2));
+        let bytes2 = self.take_bytes2();
+        if bytes2.is_empty() {
+            return;
+        }
+
+        self.scratch.reserve(self.bytes1.len() + bytes2.len());
+        self.scratch.put_slice(&self.bytes1);
+        self.scratch.put_slice(&bytes2);
+        self.bytes1 = self.scratch.split().freeze();
+    }
+
+    /// Determine if the empty line ending the headers has been buffered.
+    ///
+    /// Lines which have been fully scanned by previous calls are skipped,
+    /// including an empty line already found, so that headers which can't be
+    /// parsed yet aren't joined and parsed again until another one is buffered.
+    fn find_headers_end(&mut self) -> bool {
+        let mut line_start = self.headers_scanned;
+        let mut offset = 0;
+        let mut prev_byte = None;
+        let mut found = false;
+
+        'search: for bytes in self.buffered() {
+            let mut start = line_start.saturating_sub(offset);
+            while let Some(i) = bytes
+                .get(start..)
+                .and_then(|rest| memchr::memchr(b'\n', rest))
+            {
+                let end = start + i;
+                let line_len = offset + end - line_start;
+                let carriage_return = match end.checked_sub(1) {
+                    Some(i) => bytes[i] == b'\r',
+                    None => prev_byte == Some(b'\r'),
+                };
+                line_start = offset + end + 1;
+                if line_len == 0 || (line_len == 1 && carriage_return) {
+                    found = true;
+                    break 'search;
+                }
+
+                start = end + 1;
+            }
+
+            offset += bytes.len();
+            prev_byte = bytes.last().copied().or(prev_byte);
+        }
+
+        self.headers_scanned = line_start;
+        found
+    }
+
+    /// Parse the headers at the start of `buf`.
+    ///
+    /// Returns the length of the headers along with the headers themselves,
+    /// or `Ok(None)` if `buf` doesn't contain all of them yet.
+    /// The headers are stored in `headers_buf`, which is taken only on success.
+    fn parse_headers(
+        &self,
+        buf: &Bytes,
+        headers_buf: &mut Vec<(Bytes, Bytes)>,
+    ) -> Result<Option<(usize, RawHeaders)>, Error> {
+        if self.unfold_headers {
+            let len = match headers_len(buf) {
+                Some(len) => len,
+                None => match self.max_header_size {
+                    Some(limit) if buf.len() > limit => {
+                        return Err(Error::HeadersTooLarge { limit })
+                    }
+                    // Folded lines would make `httparse` fail before getting to the end
+                    _ => return Ok(None),
+                },
+            };
+
+            if is_folded(&buf[..len]) {
+                let unfolded = unfold(&buf[..len]);
+                let parsed = self.parse_unfolded_headers(&unfolded, headers_buf)?;
+                return Ok(parsed.map(|(_, headers)| (len, headers)));
+            }
+        }
+
+        self.parse_unfolded_headers(buf, headers_buf)
+    }
+
+    /// Parse the headers at the start of `buf` with `httparse`.
+    fn parse_unfolded_headers(
+        &self,
+        buf: &Bytes,
+        headers_buf: &mut Vec<(Bytes, Bytes)>,
+    ) -> Result<Option<(usize, RawHeaders)>, Error> {
+        match self.httparse_headers(buf, headers_buf) {
+            Err(
+                err @ Error::Headers {
+                    error: httparse::Error::HeaderName,
+                    ..
+                },
+            ) => {
+                // Some clients put whitespace between the header names and the colon,
+                // which `httparse` rejects
+                let trimmed = headers_len(buf)
+                    .and_then(|len| trim_header_names(&buf[..len]).map(|trimmed| (len, trimmed)));
+                match trimmed {
+                    Some((len, trimmed)) => {
+                        let parsed = self.httparse_headers(&trimmed, headers_buf)?;
+                        Ok(parsed.map(|(_, headers)| (len, headers)))
+                    }
+                    None => Err(err),
+                }
+            }
+            parsed => parsed,
+        }
+    }
+
+    /// Parse the headers at the start of `buf` with `httparse`, as they are.
+    fn httparse_headers(
+        &self,
+        buf: &Bytes,
+        headers_buf: &mut Vec<(Bytes, Bytes)>,
+    ) -> Result<Option<(usize, RawHeaders)>, Error> {
+        let mut headers_array = [httparse::EMPTY_HEADER; INITIAL_HEADERS];
+        let mut headers_vec = Vec::new();
+        let mut headers_len = INITIAL_HEADERS.min(self.max_headers);
+
+        // Only look for the headers inside the first `max_header_size` bytes
+        let (search, truncated) = match self.max_header_size {
+            Some(limit) if buf.len() > limit => (&buf[..limit], true),
+            _ => (&buf[..], false),
+        };
+
+        loop {
+            let headers = if headers_len <= INITIAL_HEADERS {
+                &mut headers_array[..headers_len]
+            } else {
+                headers_vec.resize(headers_len, httparse::EMPTY_HEADER);
+                &mut headers_vec[..]
+            };
+
+            match httparse::parse_headers(search, headers) {
+                Ok(httparse::Status::Complete((read, headers))) => {
+                    let too_large = headers.iter().find(|header| {
+                        header.name.len() > self.max_header_name_size
+                            || header.value.len() > self.max_header_value_size
+                    });
+                    if let Some(header) = too_large {
+                        return Err(Error::HeaderFieldTooLarge {
+                            name_len: header.name.len(),
+                            value_len: header.value.len(),
+                        });
+                    }
+
+                    let headers = if self.collect_headers {
+                        let mut collected = mem::take(headers_buf);
+                        collected.clear();
+                        collected.extend(headers.iter().map(|header| {
+                            let name = buf.slice_ref(header.name.as_bytes());
+                            let value = buf.slice_ref(header.value);
+                            (name, value)
+                        }));
+                        collected
+                    } else {
+                        Vec::new()
+                    };
+
+                    let raw = buf.slice(..read);
+                    return Ok(Some((read, RawHeaders::with_raw(headers, raw))));
+                }
+                Ok(httparse::Status::Partial) if truncated => {
+                    let limit = search.len();
+                    return Err(Error::HeadersTooLarge { limit });
+                }
+                Ok(httparse::Status::Partial) => return Ok(None),
+                Err(httparse::Error::TooManyHeaders) if headers_len < self.max_headers => {
+                    // Make more space and try again
+                    headers_len = (headers_len * 2).min(self.max_headers);
+                }
+                Err(error) => {
+                    return Err(Error::Headers {
+                        error,
+                        offset: self.consumed,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Iterate over every buffered [`Bytes`], in order.
+    fn buffered(&self) -> impl Iterator<Item = &[u8]> + Clone {
+        iter::once(&self.bytes1[..])
+            .chain(iter::once(&self.bytes2[..]))
+            .chain(self.queue.iter().map(|bytes| &bytes[..]))
+    }
+
+    /// Join every buffered [`Bytes`] into a single one.
+    ///
+    /// Unless only `bytes1` is buffered, they're copied into the scratch space.
+    fn join_buffered(&mut self) -> Bytes {
+        if self.bytes2.is_empty() {
+            return self.bytes1.clone();
+        }
+
+        self.scratch.reserve(self.buffered_len());
+        self.scratch.put_slice(&self.bytes1);
+        self.scratch.put_slice(&self.bytes2);
+        for bytes in &self.queue {
+            self.scratch.put_slice(bytes);
+        }
+        self.scratch.split().freeze()
+    }
+
+    /// Take `bytes2`, replacing it with the first queued [`Bytes`].
+    fn take_bytes2(&mut self) -> Bytes {
+        let next = self.queue.pop_front().unwrap_or_default();
+        mem::replace(&mut self.bytes2, next)
     }
 }
 
-impl Debug for FormData {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("FormData").finish()
+/// Determine if the body of the part with `headers` is text, which may start with a BOM
+fn is_text(headers: &RawHeaders) -> bool {
+    match headers.content_type() {
+        Ok(Some(content_type)) => {
+            let mime = content_type.mime.as_bytes();
+            mime.len() >= 5 && mime[..5].eq_ignore_ascii_case(b"text/")
+        }
+        Ok(None) => matches!(headers.filename(), Ok(None)),
+        Err(_) => false,
+    }
+}
+
+/// Remove the whitespace between the header names and the colon after them.
+///
+/// Returns `None` if there's no such whitespace.
+pub(crate) fn trim_header_names(headers: &[u8]) -> Option<Bytes> {
+    let mut trimmed = BytesMut::with_capacity(headers.len());
+    let mut changed = false;
+
+    let mut start = 0;
+    while start < headers.len() {
+        let end = memchr::memchr(b'\n', &headers[start..]).map_or(headers.len(), |i| start + i + 1);
+        let line = &headers[start..end];
+
+        let is_whitespace = |b: &u8| *b == b' ' || *b == b'\t';
+        let name = match memchr::memchr(b':', line) {
+            // Lines starting with whitespace are the continuation of a folded value
+            Some(colon) if !line.first().map_or(false, is_whitespace) => &line[..colon],
+            _ => &[][..],
+        };
+        let name_len = name
+            .iter()
+            .rposition(|b| !is_whitespace(b))
+            .map_or(0, |i| i + 1);
+        if name_len > 0 && name_len < name.len() {
+            trimmed.put_slice(&name[..name_len]);
+            trimmed.put_slice(&line[name.len()..]);
+            changed = true;
+        } else {
+            trimmed.put_slice(line);
+        }
+
+        start = end;
+    }
+
+    if changed {
+        Some(trimmed.freeze())
+    } else {
+        None
+    }
+}
+
+/// Find the length of the headers at the start of `buf`, including the empty line after them
+pub(crate) fn headers_len(buf: &[u8]) -> Option<usize> {
+    let mut start = 0;
+    loop {
+        let end = start + memchr::memchr(b'\n', &buf[start..])?;
+        let line = &buf[start..end];
+        if line.is_empty() || line == b"\r" {
+            return Some(end + 1);
+        }
+
+        start = end + 1;
+    }
+}
+
+/// Determine if any of the lines in `headers` continue on the next line
+fn is_folded(headers: &[u8]) -> bool {
+    headers
+        .windows(2)
+        .any(|w| w[0] == b'\n' && (w[1] == b' ' || w[1] == b'\t'))
+}
+
+/// Join the lines continuing on the next line to the previous one
+fn unfold(headers: &[u8]) -> Bytes {
+    let mut unfolded = BytesMut::with_capacity(headers.len());
+
+    let mut start = 0;
+    while let Some(i) = memchr::memchr(b'\n', &headers[start..]) {
+        let end = start + i;
+        let folded = matches!(headers.get(end + 1), Some(b' ') | Some(b'\t'));
+        if folded {
+            // Drop the line ending, keeping the whitespace at the start of the next line
+            let line = &headers[start..end];
+            match line.split_last() {
+                Some((b'\r', line)) => unfolded.put_slice(line),
+                _ => unfolded.put_slice(line),
+            }
+        } else {
+            unfolded.put_slice(&headers[start..=end]);
+        }
+
+        start = end + 1;
+    }
+    unfolded.put_slice(&headers[start..]);
+
+    unfolded.freeze()
+}
+
+/// Decode a whole multipart stream which is already in memory.
+///
+/// Returns the headers and the body of every part. Bodies borrow from `body`
+/// whenever possible.
+///
+/// ```rust
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use bytes::Bytes;
+/// use multiparty::server::sans_io::decode_all;
+///
+/// let body = Bytes::from_static(
+///     b"--abcd\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n--abcd--\r\n",
+/// );
+///
+/// let parts = decode_all("abcd", body)?;
+/// assert_eq!(parts.len(), 1);
+///
+/// let (headers, body) = &parts[0];
+/// assert_eq!(headers.parse()?.name, "foo");
+/// assert_eq!(body, "bar");
+/// # Ok(())
+/// # }
+/// ```
+pub fn decode_all(boundary: &str, body: Bytes) -> Result<Vec<(RawHeaders, Bytes)>, Error> {
+    let mut form_data = FormData::new(boundary);
+    form_data.write(body).expect("nothing has been written yet");
+    form_data.write_eof();
+
+    let mut parts = Vec::new();
+    loop {
+        match form_data.read()? {
+            Read::NewPart { headers, .. } => parts.push((headers, Bytes::new())),
+            Read::Part(bytes) => {
+                let (_, body) = parts.last_mut().expect("Part is preceded by NewPart");
+                *body = join_bytes(mem::take(body), bytes);
+            }
+            Read::PartEof | Read::None => {}
+            Read::NeedsWrite => unreachable!("eof has already been written"),
+            Read::Eof => return Ok(parts),
+        }
+    }
+}
+
+impl FormDataBuilder {
+    /// Create a new [`FormDataBuilder`] with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept at most `max_headers` headers in each part.
+    ///
+    /// See [`FormData::with_max_headers`].
+    pub fn max_header_count(mut self, max_headers: usize) -> Self {
+        self.max_headers = Some(max_headers);
+        self
+    }
+
+    /// Limit the size of the body of each part to `max_part_size` bytes.
+    ///
+    /// See [`FormData::set_max_part_size`].
+    pub fn max_part_size(mut self, max_part_size: usize) -> Self {
+        self.max_part_size = Some(max_part_size);
+        self
+    }
+
+    /// Limit the size of the body of each part without a `filename` to `max_field_size` bytes.
+    ///
+    /// See [`FormData::set_max_field_size`].
+    pub fn max_field_size(mut self, max_field_size: usize) -> Self {
+        self.max_field_size = Some(max_field_size);
+        self
+    }
+
+    /// Limit the size of the body of each part with a `filename` to `max_file_size` bytes.
+    ///
+    /// See [`FormData::set_max_file_size`].
+    pub fn max_file_size(mut self, max_file_size: usize) -> Self {
+        self.max_file_size = Some(max_file_size);
+        self
+    }
+
+    /// Limit the size of the whole multipart stream to `max_total_size` bytes.
+    ///
+    /// See [`FormData::set_max_total_size`].
+    pub fn max_total_size(mut self, max_total_size: usize) -> Self {
+        self.max_total_size = Some(max_total_size);
+        self
+    }
+
+    /// Refuse writes once `max_buffered_len` bytes are buffered.
+    ///
+    /// See [`FormData::set_max_buffered_len`].
+    pub fn max_buffered_len(mut self, max_buffered_len: usize) -> Self {
+        self.max_buffered_len = Some(max_buffered_len);
+        self
+    }
+
+    /// Limit the number of parts in the multipart stream to `max_parts`.
+    ///
+    /// See [`FormData::set_max_parts`].
+    pub fn max_parts(mut self, max_parts: usize) -> Self {
+        self.max_parts = Some(max_parts);
+        self
+    }
+
+    /// Limit the size of the headers of each part to `max_header_size` bytes.
+    ///
+    /// See [`FormData::set_max_header_size`].
+    pub fn max_header_size(mut self, max_header_size: usize) -> Self {
+        self.max_header_size = Some(max_header_size);
+        self
+    }
+
+    /// Limit the length of the name and of the value of each header.
+    ///
+    /// See [`FormData::set_max_header_field_size`].
+    pub fn max_header_field_size(
+        mut self,
+        max_header_name_size: usize,
+        max_header_value_size: usize,
+    ) -> Self {
+        self.max_header_field_size = Some((max_header_name_size, max_header_value_size));
+        self
+    }
+
+    /// Accept bare `\n` line endings in addition to `\r\n`.
+    ///
+    /// See [`FormData::set_lenient`].
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Match the boundary ignoring ASCII case.
+    ///
+    /// See [`FormData::set_boundary_ignore_ascii_case`].
+    pub fn boundary_ignore_ascii_case(mut self, boundary_ignore_ascii_case: bool) -> Self {
+        self.boundary_ignore_ascii_case = boundary_ignore_ascii_case;
+        self
+    }
+
+    /// Reject multipart streams which end without the closing boundary.
+    ///
+    /// See [`FormData::set_require_closing_boundary`].
+    pub fn require_closing_boundary(mut self, require_closing_boundary: bool) -> Self {
+        self.require_closing_boundary = require_closing_boundary;
+        self
+    }
+
+    /// Treat boundaries followed by an unexpected suffix as part of the body.
+    ///
+    /// See [`FormData::set_allow_false_boundaries`].
+    pub fn allow_false_boundaries(mut self, allow_false_boundaries: bool) -> Self {
+        self.allow_false_boundaries = allow_false_boundaries;
+        self
+    }
+
+    /// Unfold header values continuing on the next line.
+    ///
+    /// See [`FormData::set_unfold_headers`].
+    pub fn unfold_headers(mut self, unfold_headers: bool) -> Self {
+        self.unfold_headers = unfold_headers;
+        self
+    }
+
+    /// Strip the UTF-8 byte order mark some clients put at the start of text fields.
+    ///
+    /// See [`FormData::set_strip_bom`].
+    pub fn strip_bom(mut self, strip_bom: bool) -> Self {
+        self.strip_bom = strip_bom;
+        self
+    }
+
+    /// Accept and discard bytes written after the closing boundary.
+    ///
+    /// See [`FormData::set_discard_epilogue`].
+    pub fn discard_epilogue(mut self, discard_epilogue: bool) -> Self {
+        self.discard_epilogue = discard_epilogue;
+        self
+    }
+
+    /// Collect the headers of each part into the [`RawHeaders`] yielded by [`Read::NewPart`].
+    ///
+    /// See [`FormData::set_collect_headers`].
+    pub fn collect_headers(mut self, collect_headers: bool) -> Self {
+        self.collect_headers = Some(collect_headers);
+        self
+    }
+
+    /// Check that the body of parts with a `Content-Length` header is as long as declared.
+    ///
+    /// See [`FormData::set_validate_content_length`].
+    pub fn validate_content_length(mut self, validate_content_length: bool) -> Self {
+        self.validate_content_length = validate_content_length;
+        self
+    }
+
+    /// Decode the body of parts according to their `Content-Transfer-Encoding` header.
+    ///
+    /// See [`FormData::set_decode_transfer_encoding`].
+    #[cfg(feature = "cte")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cte")))]
+    pub fn decode_transfer_encoding(mut self, decode_transfer_encoding: bool) -> Self {
+        self.decode_transfer_encoding = decode_transfer_encoding;
+        self
+    }
+
+    /// Reserve `capacity` bytes of scratch space upfront.
+    ///
+    /// See [`FormData::with_capacity`].
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Build a [`FormData`] with a boundary of `boundary`.
+    pub fn build(&self, boundary: &str) -> FormData {
+        let mut form_data =
+            FormData::with_max_headers(boundary, self.max_headers.unwrap_or(DEFAULT_MAX_HEADERS));
+        form_data.scratch = BytesMut::with_capacity(self.capacity);
+        form_data.max_part_size = self.max_part_size;
+        form_data.max_field_size = self.max_field_size;
+        form_data.max_file_size = self.max_file_size;
+        form_data.max_total_size = self.max_total_size;
+        form_data.max_buffered_len = self.max_buffered_len;
+        form_data.max_parts = self.max_parts;
+        form_data.max_header_size = self.max_header_size;
+        if let Some((max_header_name_size, max_header_value_size)) = self.max_header_field_size {
+            form_data.set_max_header_field_size(max_header_name_size, max_header_value_size);
+        }
+        form_data.lenient = self.lenient;
+        form_data.boundary_ignore_ascii_case = self.boundary_ignore_ascii_case;
+        form_data.require_closing_boundary = self.require_closing_boundary;
+        form_data.allow_false_boundaries = self.allow_false_boundaries;
+        form_data.unfold_headers = self.unfold_headers;
+        form_data.strip_bom = self.strip_bom;
+        form_data.discard_epilogue = self.discard_epilogue;
+        if let Some(collect_headers) = self.collect_headers {
+            form_data.collect_headers = collect_headers;
+        }
+        form_data.validate_content_length = self.validate_content_length;
+        #[cfg(feature = "cte")]
+        {
+            form_data.decode_transfer_encoding = self.decode_transfer_encoding;
+        }
+        form_data
+    }
+}
+
+impl Debug for FormData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FormData").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDARY: &str = "--abcdef1234--";
+
+    fn body(parts: &[(&str, &str)]) -> Bytes {
+        let mut body = String::new();
+        for (name, contents) in parts {
+            body.push_str(&format!(
+                "--{}\r\ncontent-disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                BOUNDARY, name, contents
+            ));
+        }
+        body.push_str(&format!("--{}--\r\n", BOUNDARY));
+        body.into()
+    }
+
+    /// Decode `body` by feeding it `chunk_size` bytes at a time,
+    /// returning the body of every part.
+    fn decode(
+        mut form_data: FormData,
+        body: &[u8],
+        chunk_size: usize,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let mut chunks = body.chunks(chunk_size);
+        let mut parts = Vec::new();
+        loop {
+            match form_data.read()? {
+                Read::NeedsWrite => match chunks.next() {
+                    Some(chunk) => form_data.write(Bytes::copy_from_slice(chunk)).unwrap(),
+                    None => form_data.write_eof(),
+                },
+                Read::NewPart { .. } => parts.push(Vec::new()),
+                Read::Part(bytes) => parts.last_mut().unwrap().extend_from_slice(&bytes),
+                Read::PartEof | Read::None => {}
+                Read::Eof => return Ok(parts),
+            }
+        }
+    }
+
+    /// Drive `form_data`, which has already been fed the whole body,
+    /// until it yields something meaningful.
+    fn read(form_data: &mut FormData) -> Result<Read, Error> {
+        loop {
+            match form_data.read() {
+                Ok(Read::None) => {}
+                Ok(Read::NeedsWrite) => form_data.write_eof(),
+                read => return read,
+            }
+        }
+    }
+
+    #[test]
+    fn chunked() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh"), ("baz", "")]);
+
+        for chunk_size in 1..=body.len() {
+            let parts = decode(FormData::new(BOUNDARY), &body, chunk_size).unwrap();
+            assert_eq!(parts, vec![b"bar".to_vec(), b"efgh".to_vec(), b"".to_vec()]);
+        }
+    }
+
+    #[test]
+    fn headers_completed_by_last_chunk() {
+        let body = body(&[("foo", "bar")]);
+        let headers_end = memchr::memmem::find(&body, b"\r\n\r\n").unwrap();
+
+        // Every byte is written before reading, with the end of
+        // the headers only being in the second and last chunk
+        for split in BOUNDARY.len() + 4..=headers_end {
+            let mut form_data = FormData::new(BOUNDARY);
+            form_data.write(body.slice(..split)).unwrap();
+            form_data.write(body.slice(split..)).unwrap();
+            form_data.write_eof();
+
+            assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+            assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == "bar"));
+            assert!(matches!(read(&mut form_data), Ok(Read::PartEof)));
+            assert!(matches!(read(&mut form_data), Ok(Read::Eof)));
+        }
+    }
+
+    #[test]
+    fn near_boundary_contents() {
+        // Everything but the last byte of the delimiter
+        let partial = format!("\r\n--{}", &BOUNDARY[..BOUNDARY.len() - 1]);
+        let contents = [
+            format!("--{}", BOUNDARY),
+            format!("\n--{}", BOUNDARY),
+            format!("\r--{}", BOUNDARY),
+            format!("--{}--\r\n", BOUNDARY),
+            partial.clone(),
+            format!("{}X", partial),
+            format!("{0}{0}", partial),
+            format!("\r\n\r\n--{}\r", &BOUNDARY[..BOUNDARY.len() - 3]),
+            format!("abc\r{}", partial),
+            "\r\n-".to_owned(),
+            "\r\n".to_owned(),
+            "\r".to_owned(),
+        ];
+
+        for lenient in [false, true].iter().copied() {
+            for content in &contents {
+                if lenient && content.starts_with('\n') {
+                    // Lenient mode accepts a bare `\n` before the delimiter
+                    continue;
+                }
+
+                let body = body(&[("foo", content), ("bar", "baz")]);
+                let expected = vec![content.as_bytes().to_vec(), b"baz".to_vec()];
+
+                for chunk_size in 1..=body.len() {
+                    let mut form_data = FormData::new(BOUNDARY);
+                    form_data.set_lenient(lenient);
+                    let parts = decode(form_data, &body, chunk_size).unwrap();
+                    assert_eq!(parts, expected, "{:?} {}", content, chunk_size);
+                }
+
+                // Split the body between `bytes1` and `bytes2` at every position
+                for split in 1..body.len() {
+                    let mut form_data = FormData::new(BOUNDARY);
+                    form_data.set_lenient(lenient);
+                    form_data.write(body.slice(..split)).unwrap();
+                    form_data.write(body.slice(split..)).unwrap();
+
+                    let mut parts = Vec::<Vec<u8>>::new();
+                    loop {
+                        match read(&mut form_data).unwrap() {
+                            Read::NewPart { .. } => parts.push(Vec::new()),
+                            Read::Part(bytes) => {
+                                parts.last_mut().unwrap().extend_from_slice(&bytes)
+                            }
+                            Read::PartEof => {}
+                            Read::Eof => break,
+                            Read::NeedsWrite | Read::None => unreachable!(),
+                        }
+                    }
+                    assert_eq!(parts, expected, "{:?} {}", content, split);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn line_break_in_header_value() {
+        // `filename` contains a line break which the client didn't encode
+        for filename in &["a\r\nb.txt", "a\r\nb: c.txt"] {
+            let body = format!(
+                "--{0}\r\ncontent-disposition: form-data; name=\"foo\"; filename=\"{1}\"\r\n\r\n\
+                 bar\r\n--{0}--\r\n",
+                BOUNDARY, filename
+            );
+
+            let mut form_data = FormData::new(BOUNDARY);
+            form_data.write(body.into()).unwrap();
+            match read(&mut form_data) {
+                Err(Error::Headers { .. }) => {}
+                Ok(Read::NewPart { headers, .. }) => assert!(headers.parse().is_err()),
+                read => panic!("unexpected {:?}", read),
+            }
+        }
+    }
+
+    #[test]
+    fn write_slice() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh")]);
+
+        for chunk_size in 1..=body.len() {
+            let mut form_data = FormData::with_capacity(BOUNDARY, 64);
+            let mut chunks = body.chunks(chunk_size);
+            let mut parts = Vec::new();
+            loop {
+                match form_data.read().unwrap() {
+                    Read::NeedsWrite => match chunks.next() {
+                        Some(chunk) => form_data.write_slice(chunk).unwrap(),
+                        None => form_data.write_eof(),
+                    },
+                    Read::NewPart { .. } => parts.push(Vec::new()),
+                    Read::Part(bytes) => parts.last_mut().unwrap().extend_from_slice(&bytes),
+                    Read::PartEof | Read::None => {}
+                    Read::Eof => break,
+                }
+            }
+            assert_eq!(parts, vec![b"bar".to_vec(), b"efgh".to_vec()]);
+        }
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.write_eof();
+        let err = form_data.write_slice(b"abcd").unwrap_err();
+        assert_eq!(err.kind(), WriteErrorKind::EofWritten);
+        assert_eq!(err.into_bytes(), "abcd");
+    }
+
+    #[test]
+    fn saw_any_boundary() {
+        for (body, saw_any_boundary) in &[
+            ("".to_owned(), false),
+            ("not multipart".to_owned(), false),
+            (format!("--{}--\r\n", BOUNDARY), true),
+            (format!("preamble\r\n--{}--\r\n", BOUNDARY), true),
+        ] {
+            let mut form_data = FormData::new(BOUNDARY);
+            if !body.is_empty() {
+                form_data.write(body.clone().into()).unwrap();
+            }
+            assert!(!form_data.saw_any_boundary());
+            assert!(matches!(read(&mut form_data), Ok(Read::Eof)));
+            assert_eq!(
+                form_data.saw_any_boundary(),
+                *saw_any_boundary,
+                "{:?}",
+                body
+            );
+
+            form_data.reset();
+            assert!(!form_data.saw_any_boundary());
+        }
+    }
+
+    #[test]
+    fn require_closing_boundary() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh")]);
+        let closing = format!("\r\n--{}--\r\n", BOUNDARY).len();
+
+        for len in [
+            body.len() - closing,
+            body.len() - closing + 5,
+            body.len() - 3,
+        ]
+        .iter()
+        .copied()
+        {
+            for chunk_size in 1..=len {
+                let form_data = FormData::builder()
+                    .require_closing_boundary(true)
+                    .build(BOUNDARY);
+                assert_eq!(
+                    decode(form_data, &body[..len], chunk_size),
+                    Err(Error::UnexpectedEof)
+                );
+            }
+        }
+
+        let len = body.len() - closing;
+        let parts = decode(FormData::new(BOUNDARY), &body[..len], len).unwrap();
+        assert_eq!(parts, vec![b"bar".to_vec(), b"efgh".to_vec()]);
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_require_closing_boundary(true);
+        let mut parts = Vec::new();
+        form_data.write(body.slice(..body.len() - closing)).unwrap();
+        form_data.write_eof();
+        let err = loop {
+            match form_data.read() {
+                Ok(Read::NewPart { .. }) => parts.push(Vec::new()),
+                Ok(Read::Part(bytes)) => parts.last_mut().unwrap().extend_from_slice(&bytes),
+                Ok(_) => {}
+                Err(err) => break err,
+            }
+        };
+        assert_eq!(err, Error::UnexpectedEof);
+        assert!(form_data.was_truncated());
+        assert!(matches!(form_data.read(), Ok(Read::Eof)));
+        // The body held back while looking for the boundary isn't flushed
+        assert!(parts[1].len() < b"efgh".len());
+
+        let form_data = FormData::builder()
+            .require_closing_boundary(true)
+            .build(BOUNDARY);
+        assert_eq!(
+            decode(form_data, &body, body.len()),
+            Ok(vec![b"bar".to_vec(), b"efgh".to_vec()])
+        );
+    }
+
+    #[test]
+    fn on_event() {
+        use std::sync::{Arc, Mutex};
+
+        let body = body(&[("foo", "bar"), ("abcd", "efgh")]);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_on_event({
+            let events = Arc::clone(&events);
+            move |event| {
+                let event = match event {
+                    Event::NewPart { headers, .. } => format!("new {}", headers.name().unwrap()),
+                    Event::Part { len } => format!("part {}", len),
+                    Event::PartEof => "part eof".to_owned(),
+                    Event::Eof => "eof".to_owned(),
+                };
+                events.lock().unwrap().push(event);
+            }
+        });
+
+        form_data.write(body).unwrap();
+        while !matches!(read(&mut form_data), Ok(Read::Eof)) {}
+        assert!(matches!(read(&mut form_data), Ok(Read::Eof)));
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            ["new foo", "part 3", "part eof", "new abcd", "part 4", "part eof", "eof"]
+        );
+    }
+
+    #[test]
+    fn on_event_max_total_size() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let body = body(&[("foo", "bar")]);
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_max_total_size(20);
+        let events = Arc::new(AtomicUsize::new(0));
+        form_data.set_on_event({
+            let events = Arc::clone(&events);
+            move |_| {
+                events.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        form_data.write(body).unwrap();
+        assert!(matches!(
+            read(&mut form_data),
+            Err(Error::TotalSizeExceeded { limit: 20 })
+        ));
+        assert_eq!(events.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn write_and_read() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh")]);
+
+        let mut form_data = FormData::new(BOUNDARY);
+        let mut parts = Vec::new();
+        for chunk in body.chunks(7) {
+            for read in form_data
+                .write_and_read(Bytes::copy_from_slice(chunk))
+                .unwrap()
+            {
+                match read.unwrap() {
+                    Read::NewPart { .. } => parts.push(Vec::new()),
+                    Read::Part(bytes) => parts.last_mut().unwrap().extend_from_slice(&bytes),
+                    Read::PartEof | Read::Eof => {}
+                    Read::NeedsWrite | Read::None => unreachable!(),
+                }
+            }
+        }
+        assert_eq!(parts, vec![b"bar".to_vec(), b"efgh".to_vec()]);
+        assert!(matches!(form_data.read(), Ok(Read::Eof)));
+
+        let mut form_data = FormData::new(BOUNDARY);
+        let mut reads = form_data
+            .write_and_read(Bytes::from_static(b"--abcd\r\n"))
+            .unwrap();
+        assert!(reads.next().is_none());
+        assert!(reads.next().is_none());
+
+        // An epilogue arriving after the closing boundary
+        let body = format!("--{0}\r\n\r\nbar\r\n--{0}--\r\n", BOUNDARY);
+        let mut form_data = FormData::new(BOUNDARY);
+        let reads = form_data.write_and_read(Bytes::from(body)).unwrap().count();
+        assert_eq!(reads, 4);
+        let err = form_data
+            .write_and_read(Bytes::from_static(b"epilogue"))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), WriteErrorKind::Finished);
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_discard_epilogue(true);
+        let body = format!("--{}--\r\n", BOUNDARY);
+        let reads = form_data.write_and_read(Bytes::from(body)).unwrap();
+        assert!(matches!(reads.last(), Some(Ok(Read::Eof))));
+        let mut reads = form_data
+            .write_and_read(Bytes::from_static(b"epilogue"))
+            .unwrap();
+        assert!(matches!(reads.next(), Some(Ok(Read::Eof))));
+        assert!(reads.next().is_none());
+    }
+
+    #[test]
+    fn pending() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh")]);
+
+        let mut form_data = FormData::pending();
+        assert_eq!(form_data.read().unwrap_err(), Error::EmptyBoundary);
+        form_data.set_boundary(BOUNDARY).unwrap();
+        assert_eq!(form_data.boundary(), BOUNDARY);
+        assert_eq!(
+            decode(form_data, &body, 7),
+            Ok(vec![b"bar".to_vec(), b"efgh".to_vec()])
+        );
+
+        let mut form_data = FormData::pending();
+        form_data.set_boundary("abcd").unwrap();
+        form_data.write(body.clone()).unwrap();
+        assert_eq!(form_data.set_boundary(BOUNDARY), Err(SetBoundaryError(())));
+        assert_eq!(form_data.boundary(), "abcd");
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.write(body).unwrap();
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert_eq!(form_data.set_boundary("abcd"), Err(SetBoundaryError(())));
+    }
+
+    #[test]
+    fn empty_boundary() {
+        let body = b"--\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n----\r\n";
+
+        assert_eq!(
+            decode(FormData::new(""), body, body.len()),
+            Err(Error::EmptyBoundary)
+        );
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.reset_with_boundary("");
+        assert_eq!(form_data.read().unwrap_err(), Error::EmptyBoundary);
+    }
+
+    #[test]
+    fn crlf_contents() {
+        let contents = [
+            "\r\n",
+            "\r\n\r\n",
+            "bar\r\n",
+            "bar\r\n\r\n",
+            "\r\nbar",
+            "\r\n\r\n\r\n",
+            "\n",
+            "\r",
+            "\n\r",
+        ];
+
+        for lenient in [false, true].iter().copied() {
+            for content in &contents {
+                // The line break before the boundary must not be part of the body,
+                // while those inside the body must be yielded as they are
+                let body = body(&[("foo", content), ("bar", content), ("baz", "")]);
+                let expected = vec![
+                    content.as_bytes().to_vec(),
+                    content.as_bytes().to_vec(),
+                    Vec::new(),
+                ];
+
+                for chunk_size in 1..=body.len() {
+                    let mut form_data = FormData::new(BOUNDARY);
+                    form_data.set_lenient(lenient);
+                    let parts = decode(form_data, &body, chunk_size).unwrap();
+                    assert_eq!(parts, expected, "{:?} {} {}", content, lenient, chunk_size);
+
+                    // Every chunk is written upfront, so most of them get queued
+                    let mut form_data = FormData::new(BOUNDARY);
+                    form_data.set_lenient(lenient);
+                    for chunk in body.chunks(chunk_size) {
+                        form_data.write(Bytes::copy_from_slice(chunk)).unwrap();
+                    }
+                    let mut parts = Vec::<Vec<u8>>::new();
+                    loop {
+                        match read(&mut form_data).unwrap() {
+                            Read::NewPart { .. } => parts.push(Vec::new()),
+                            Read::Part(bytes) => {
+                                parts.last_mut().unwrap().extend_from_slice(&bytes)
+                            }
+                            Read::PartEof => {}
+                            Read::Eof => break,
+                            Read::NeedsWrite | Read::None => unreachable!(),
+                        }
+                    }
+                    assert_eq!(parts, expected, "{:?} {} {}", content, lenient, chunk_size);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn boundary_ignore_ascii_case() {
+        let body = format!(
+            "--{0}\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n\
+             --{1}\r\ncontent-disposition: form-data; name=\"abcd\"\r\n\r\nefgh\r\n\
+             --{1}--\r\n",
+            BOUNDARY.to_ascii_uppercase(),
+            BOUNDARY,
+        );
+
+        for chunk_size in 1..=body.len() {
+            let form_data = FormData::builder()
+                .boundary_ignore_ascii_case(true)
+                .build(BOUNDARY);
+            let parts = decode(form_data, body.as_bytes(), chunk_size).unwrap();
+            assert_eq!(parts, vec![b"bar".to_vec(), b"efgh".to_vec()]);
+        }
+
+        // The first boundary doesn't match, so everything is skipped as preamble
+        let parts = decode(FormData::new(BOUNDARY), body.as_bytes(), body.len()).unwrap();
+        assert_eq!(parts, vec![b"efgh".to_vec()]);
+    }
+
+    #[cfg(feature = "cte")]
+    #[test]
+    fn base64_transfer_encoding() {
+        let body = format!(
+            "--{0}\r\ncontent-disposition: form-data; name=\"foo\"\r\n\
+             content-transfer-encoding: base64\r\n\r\nZm9v\r\nYmE=\r\n--{0}\r\n\
+             content-disposition: form-data; name=\"abcd\"\r\n\
+             content-transfer-encoding: 8bit\r\n\r\nZm9v\r\n--{0}--\r\n",
+            BOUNDARY
+        );
+
+        for chunk_size in 1..=body.len() {
+            let mut form_data = FormData::new(BOUNDARY);
+            form_data.set_decode_transfer_encoding(true);
+            let parts = decode(form_data, body.as_bytes(), chunk_size).unwrap();
+            assert_eq!(parts, vec![b"fooba".to_vec(), b"Zm9v".to_vec()]);
+        }
+
+        let parts = decode(FormData::new(BOUNDARY), body.as_bytes(), body.len()).unwrap();
+        assert_eq!(parts, vec![b"Zm9v\r\nYmE=".to_vec(), b"Zm9v".to_vec()]);
+
+        // Limits count the encoded bytes
+        let builder = FormData::builder()
+            .decode_transfer_encoding(true)
+            .max_part_size(9);
+        let err = decode(builder.build(BOUNDARY), body.as_bytes(), 7).unwrap_err();
+        assert_eq!(err, Error::PartTooLarge { limit: 9 });
+
+        let builder = FormData::builder()
+            .decode_transfer_encoding(true)
+            .max_part_size(10);
+        let parts = decode(builder.build(BOUNDARY), body.as_bytes(), 7).unwrap();
+        assert_eq!(parts, vec![b"fooba".to_vec(), b"Zm9v".to_vec()]);
+    }
+
+    #[cfg(feature = "cte")]
+    #[test]
+    fn invalid_transfer_encoding() {
+        let body = |encoding: &str, contents: &str| {
+            format!(
+                "--{0}\r\ncontent-disposition: form-data; name=\"foo\"\r\n\
+                 content-transfer-encoding: {1}\r\n\r\n{2}\r\n--{0}--\r\n",
+                BOUNDARY, encoding, contents
+            )
+        };
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_decode_transfer_encoding(true);
+        let body1 = body("quoted-printable", "foo=3D");
+        assert_eq!(
+            decode(form_data, body1.as_bytes(), body1.len()),
+            Err(Error::UnsupportedTransferEncoding)
+        );
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_decode_transfer_encoding(true);
+        let body2 = body("base64", "Zm9v!");
+        assert_eq!(
+            decode(form_data, body2.as_bytes(), body2.len()),
+            Err(Error::InvalidBase64)
+        );
+    }
+
+    #[test]
+    fn current_part_len() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh")]);
+
+        for chunk_size in 1..=body.len() {
+            let mut form_data = FormData::new(BOUNDARY);
+            let mut chunks = body.chunks(chunk_size);
+            let mut lens = Vec::new();
+            loop {
+                match form_data.read().unwrap() {
+                    Read::NeedsWrite => match chunks.next() {
+                        Some(chunk) => form_data.write(Bytes::copy_from_slice(chunk)).unwrap(),
+                        None => form_data.write_eof(),
+                    },
+                    Read::NewPart { .. } => {
+                        assert_eq!(form_data.current_part_len(), 0);
+                        lens.push(0);
+                    }
+                    Read::Part(_) => *lens.last_mut().unwrap() = form_data.current_part_len(),
+                    Read::PartEof => assert_eq!(form_data.current_part_len(), 0),
+                    Read::None => {}
+                    Read::Eof => break,
+                }
+            }
+            assert_eq!(lens, vec![3, 4]);
+        }
+    }
+
+    #[test]
+    fn reset() {
+        let body1 = body(&[("foo", "bar")]);
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_max_part_size(4);
+
+        let truncated = body1.windows(3).position(|w| w == b"bar").unwrap() + 2;
+        form_data.write(body1.slice(..truncated)).unwrap();
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == "ba"));
+
+        form_data.reset();
+        assert_eq!(decode(form_data, &body1, 3), Ok(vec![b"bar".to_vec()]));
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_max_part_size(4);
+        form_data.write(body1).unwrap();
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+
+        form_data.reset_with_boundary("abcd");
+        assert_eq!(form_data.boundary(), "abcd");
+        let body2 =
+            "--abcd\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbarbaz\r\n--abcd--\r\n";
+        assert_eq!(
+            decode(form_data, body2.as_bytes(), body2.len()),
+            Err(Error::PartTooLarge { limit: 4 })
+        );
+    }
+
+    #[test]
+    fn field_and_file_size() {
+        let body = "--abcd\r\n\
+                    content-disposition: form-data; name=\"foo\"\r\n\r\n\
+                    bar\r\n\
+                    --abcd\r\n\
+                    content-disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\r\n\
+                    efghijkl\r\n\
+                    --abcd--\r\n";
+        let expected = vec![b"bar".to_vec(), b"efghijkl".to_vec()];
+
+        let mut form_data = FormData::new("abcd");
+        form_data.set_max_field_size(3);
+        form_data.set_max_file_size(8);
+        assert_eq!(decode(form_data, body.as_bytes(), 5), Ok(expected.clone()));
+
+        let mut form_data = FormData::new("abcd");
+        form_data.set_max_field_size(2);
+        form_data.set_max_file_size(8);
+        assert_eq!(
+            decode(form_data, body.as_bytes(), 5),
+            Err(Error::PartTooLarge { limit: 2 })
+        );
+
+        // `max_part_size` applies to the parts without a more specific limit
+        let mut form_data = FormData::new("abcd");
+        form_data.set_max_part_size(3);
+        form_data.set_max_field_size(3);
+        assert_eq!(
+            decode(form_data, body.as_bytes(), 5),
+            Err(Error::PartTooLarge { limit: 3 })
+        );
+
+        let mut form_data = FormData::new("abcd");
+        form_data.set_max_part_size(3);
+        form_data.set_max_file_size(8);
+        assert_eq!(decode(form_data, body.as_bytes(), 5), Ok(expected));
+
+        let form_data = FormData::builder()
+            .max_field_size(8)
+            .max_file_size(7)
+            .build("abcd");
+        assert_eq!(
+            decode(form_data, body.as_bytes(), 5),
+            Err(Error::PartTooLarge { limit: 7 })
+        );
+    }
+
+    #[test]
+    fn headers_not_rescanned() {
+        let body = body(&[("foo", "bar")]);
+        let headers_start = BOUNDARY.len() + 4;
+
+        let mut form_data = FormData::new(BOUNDARY);
+        let mut scanned = Vec::new();
+        for chunk in body.chunks(1) {
+            form_data.write(Bytes::copy_from_slice(chunk)).unwrap();
+            let mut read = form_data.read().unwrap();
+            while let Read::None = read {
+                read = form_data.read().unwrap();
+            }
+            match read {
+                Read::NeedsWrite if form_data.state == State::Headers => {
+                    // The buffered headers aren't joined until their end is found
+                    assert_eq!(form_data.scratch.capacity(), 0);
+                    scanned.push(form_data.headers_scanned)
+                }
+                Read::NeedsWrite => {}
+                Read::NewPart { headers, .. } => {
+                    assert_eq!(headers.parse().unwrap().name, "foo");
+                    break;
+                }
+                read => panic!("unexpected {:?}", read),
+            }
+        }
+
+        // The scan only restarts from the start of the line being written
+        let line_len = "content-disposition: form-data; name=\"foo\"\r\n".len();
+        assert_eq!(scanned.first(), Some(&0));
+        assert_eq!(scanned.last(), Some(&line_len));
+        assert!(scanned.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(form_data.headers_scanned, 0);
+
+        // The empty line is split across `Bytes`
+        for chunk_size in 1..=body.len() - headers_start {
+            let parts = decode(FormData::new(BOUNDARY), &body, chunk_size).unwrap();
+            assert_eq!(parts, vec![b"bar".to_vec()]);
+        }
+    }
+
+    #[test]
+    fn content_length() {
+        fn body(content_length: usize) -> String {
+            format!(
+                "--abcd\r\n\
+                 content-disposition: form-data; name=\"foo\"\r\n\
+                 content-length: {}\r\n\r\n\
+                 bar\r\n\
+                 --abcd--\r\n",
+                content_length
+            )
+        }
+
+        for &chunk_size in &[1, 7, 100] {
+            let mut form_data = FormData::new("abcd");
+            form_data.set_validate_content_length(true);
+            let parts = decode(form_data, body(3).as_bytes(), chunk_size);
+            assert_eq!(parts, Ok(vec![b"bar".to_vec()]));
+
+            let mut form_data = FormData::new("abcd");
+            form_data.set_validate_content_length(true);
+            assert_eq!(
+                decode(form_data, body(4).as_bytes(), chunk_size),
+                Err(Error::PartLengthMismatch {
+                    expected: 4,
+                    actual: 3
+                })
+            );
+
+            let mut form_data = FormData::new("abcd");
+            form_data.set_validate_content_length(true);
+            assert!(matches!(
+                decode(form_data, body(2).as_bytes(), chunk_size),
+                Err(Error::PartLengthMismatch { expected: 2, .. })
+            ));
+
+            // Not validated by default
+            let parts = decode(FormData::new("abcd"), body(2).as_bytes(), chunk_size);
+            assert_eq!(parts, Ok(vec![b"bar".to_vec()]));
+        }
+
+        let mut form_data = FormData::new("abcd");
+        form_data.write(Bytes::from(body(3))).unwrap();
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        // The header can't be trusted without a limit
+        assert_eq!(form_data.content_length_hint(), None);
+
+        let mut form_data = FormData::new("abcd");
+        form_data.set_max_part_size(3);
+        form_data.write(Bytes::from(body(3))).unwrap();
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert_eq!(form_data.content_length_hint(), Some(3));
+
+        let mut form_data = FormData::new("abcd");
+        form_data.set_max_part_size(2);
+        form_data.write(Bytes::from(body(3))).unwrap();
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert_eq!(form_data.content_length_hint(), None);
+    }
+
+    #[test]
+    fn header_field_too_large() {
+        fn body(value_len: usize) -> String {
+            format!(
+                "--abcd\r\n\
+                 content-disposition: form-data; name=\"foo\"\r\n\
+                 x-long: {}\r\n\r\n\
+                 bar\r\n\
+                 --abcd--\r\n",
+                "a".repeat(value_len)
+            )
+        }
+
+        let body1 = body(DEFAULT_MAX_HEADER_FIELD_SIZE);
+        let parts = decode(FormData::new("abcd"), body1.as_bytes(), 1000);
+        assert_eq!(parts, Ok(vec![b"bar".to_vec()]));
+
+        let body2 = body(DEFAULT_MAX_HEADER_FIELD_SIZE + 1);
+        assert_eq!(
+            decode(FormData::new("abcd"), body2.as_bytes(), 1000),
+            Err(Error::HeaderFieldTooLarge {
+                name_len: 6,
+                value_len: DEFAULT_MAX_HEADER_FIELD_SIZE + 1
+            })
+        );
+
+        let mut form_data = FormData::new("abcd");
+        form_data.set_max_header_field_size(64, DEFAULT_MAX_HEADER_FIELD_SIZE + 1);
+        let parts = decode(form_data, body2.as_bytes(), 1000);
+        assert_eq!(parts, Ok(vec![b"bar".to_vec()]));
+
+        let form_data = FormData::builder()
+            .max_header_field_size(5, 1024)
+            .build("abcd");
+        assert_eq!(
+            decode(form_data, body(3).as_bytes(), 7),
+            Err(Error::HeaderFieldTooLarge {
+                name_len: 19,
+                value_len: 21
+            })
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh")]);
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.write(body.clone()).unwrap();
+        while !matches!(read(&mut form_data), Ok(Read::Eof)) {}
+        assert!(!form_data.was_truncated());
+
+        // Cut in the middle of a part, right after a part and inside the headers
+        let cut_after = |pat: &str| {
+            let pos = body.windows(pat.len()).position(|w| w == pat.as_bytes());
+            pos.unwrap() + pat.len()
+        };
+        for &len in &[
+            cut_after("\r\n\r\nef"),
+            cut_after("\r\n\r\n"),
+            cut_after("content-"),
+        ] {
+            let mut form_data = FormData::new(BOUNDARY);
+            form_data.write(body.slice(..len)).unwrap();
+            while let Ok(read) = read(&mut form_data) {
+                if matches!(read, Read::Eof) {
+                    break;
+                }
+            }
+            assert!(form_data.was_truncated(), "{}", len);
+        }
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.write_eof();
+        assert!(matches!(read(&mut form_data), Ok(Read::Eof)));
+        assert!(!form_data.was_truncated());
+    }
+
+    #[test]
+    fn peek() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh"), ("baz", "")]);
+
+        for &lenient in &[false, true] {
+            for chunk_size in 1..=body.len() {
+                let mut form_data = FormData::new(BOUNDARY);
+                form_data.set_lenient(lenient);
+                let mut chunks = body.chunks(chunk_size);
+                let mut peeked = 0;
+
+                loop {
+                    let peek = form_data.peek();
+                    let read = form_data.read().unwrap();
+                    let kind = match &read {
+                        Read::NeedsWrite => ReadKind::NeedsWrite,
+                        Read::NewPart { .. } => ReadKind::NewPart,
+                        Read::Part(_) => ReadKind::Part,
+                        Read::PartEof => ReadKind::PartEof,
+                        Read::Eof => ReadKind::Eof,
+                        Read::None => {
+                            assert_eq!(peek, None);
+                            continue;
+                        }
+                    };
+                    if let Some(peek) = peek {
+                        assert_eq!(peek, kind);
+                        peeked += 1;
+                    }
+
+                    match read {
+                        Read::NeedsWrite => match chunks.next() {
+                            Some(chunk) => form_data.write(Bytes::copy_from_slice(chunk)).unwrap(),
+                            None => form_data.write_eof(),
+                        },
+                        Read::Eof => break,
+                        _ => {}
+                    }
+                }
+
+                assert!(peeked > 0);
+                assert_eq!(form_data.peek(), Some(ReadKind::Eof));
+            }
+        }
+    }
+
+    #[test]
+    fn peek_truncated() {
+        let body = format!(
+            "--{}\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nhello",
+            BOUNDARY
+        );
+
+        for &(strip_bom, require_closing_boundary) in
+            &[(false, false), (true, false), (false, true)]
+        {
+            for chunk_size in 1..=body.len() {
+                let mut form_data = FormData::new(BOUNDARY);
+                form_data.set_strip_bom(strip_bom);
+                form_data.set_require_closing_boundary(require_closing_boundary);
+                let mut chunks = body.as_bytes().chunks(chunk_size);
+
+                loop {
+                    let peek = form_data.peek();
+                    let kind = match form_data.read() {
+                        Ok(Read::NeedsWrite) => {
+                            match chunks.next() {
+                                Some(chunk) => {
+                                    form_data.write(Bytes::copy_from_slice(chunk)).unwrap()
+                                }
+                                None => form_data.write_eof(),
+                            }
+                            ReadKind::NeedsWrite
+                        }
+                        Ok(Read::NewPart { .. }) => ReadKind::NewPart,
+                        Ok(Read::Part(_)) => ReadKind::Part,
+                        Ok(Read::PartEof) => ReadKind::PartEof,
+                        Ok(Read::Eof) => {
+                            assert_eq!(peek, Some(ReadKind::Eof));
+                            assert!(form_data.was_truncated());
+                            break;
+                        }
+                        Ok(Read::None) | Err(_) => {
+                            assert_eq!(peek, None);
+                            if form_data.state == State::Eof {
+                                break;
+                            }
+                            continue;
+                        }
+                    };
+                    if let Some(peek) = peek {
+                        assert_eq!(peek, kind);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn buffered_len() {
+        let body = body(&[("foo", "bar")]);
+
+        let mut form_data = FormData::new(BOUNDARY);
+        assert_eq!(form_data.buffered_len(), 0);
+        assert!(form_data.has_capacity());
+
+        for chunk in body.chunks(10) {
+            form_data.write(Bytes::copy_from_slice(chunk)).unwrap();
+        }
+        assert_eq!(form_data.buffered_len(), body.len());
+        assert!(form_data.has_capacity());
+
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert!(form_data.buffered_len() < body.len());
+
+        form_data.write_eof();
+        assert!(!form_data.has_capacity());
+        while !matches!(read(&mut form_data), Ok(Read::Eof)) {}
+        assert!(!form_data.has_capacity());
+    }
+
+    #[test]
+    fn max_buffered_len() {
+        let body = body(&[("foo", "bar"), ("abcd", "efghijklmnopqrstuvwxyz")]);
+
+        let mut form_data = FormData::builder().max_buffered_len(20).build(BOUNDARY);
+        form_data.write(body.slice(..10)).unwrap();
+        form_data.write(body.slice(10..20)).unwrap();
+        assert!(!form_data.has_capacity());
+        let err = form_data.write(body.slice(20..30)).unwrap_err();
+        assert_eq!(err.kind(), WriteErrorKind::Full);
+        assert_eq!(err.into_bytes(), body.slice(20..30));
+        assert_eq!(form_data.buffered_len(), 20);
+
+        for chunk_size in 1..12 {
+            let mut form_data = FormData::new(BOUNDARY);
+            form_data.set_max_buffered_len(8);
+            let mut offset = 0;
+            let mut parts = Vec::new();
+
+            loop {
+                // Write ahead as long as there's space
+                while offset < body.len() && form_data.has_capacity() {
+                    let end = (offset + chunk_size).min(body.len());
+                    form_data.write(body.slice(offset..end)).unwrap();
+                    offset = end;
+                }
+
+                match form_data.read().unwrap() {
+                    Read::NeedsWrite if offset < body.len() => {
+                        // Always accepted, even if the buffer is full
+                        let end = (offset + chunk_size).min(body.len());
+                        form_data.write(body.slice(offset..end)).unwrap();
+                        offset = end;
+                    }
+                    Read::NeedsWrite => form_data.write_eof(),
+                    Read::Part(bytes) => parts.push(bytes),
+                    Read::Eof => break,
+                    _ => {}
+                }
+            }
+
+            assert_eq!(parts.concat(), b"barefghijklmnopqrstuvwxyz");
+        }
+    }
+
+    #[test]
+    fn tiny_chunks_zero_copy() {
+        let body = body(&[("foo", "bar"), ("abcd", "efghijklmnopqrstuvwxyz")]);
+        let range = body.as_ptr() as usize..body.as_ptr() as usize + body.len();
+
+        for &lenient in &[false, true] {
+            for chunk_size in 1..8 {
+                let mut form_data = FormData::new(BOUNDARY);
+                form_data.set_lenient(lenient);
+                let mut offset = 0;
+                let mut parts = Vec::new();
+
+                loop {
+                    match form_data.read().unwrap() {
+                        Read::NeedsWrite if offset < body.len() => {
+                            let end = (offset + chunk_size).min(body.len());
+                            form_data.write(body.slice(offset..end)).unwrap();
+                            offset = end;
+                        }
+                        Read::NeedsWrite => form_data.write_eof(),
+                        Read::NewPart { .. } => parts.push(Vec::new()),
+                        Read::Part(bytes) => {
+                            // The body of the parts points into the original `Bytes`
+                            assert!(range.contains(&(bytes.as_ptr() as usize)));
+                            parts.last_mut().unwrap().extend_from_slice(&bytes);
+                        }
+                        Read::PartEof | Read::None => {}
+                        Read::Eof => break,
+                    }
+                }
+
+                assert_eq!(
+                    parts,
+                    vec![b"bar".to_vec(), b"efghijklmnopqrstuvwxyz".to_vec()]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn header_name_whitespace() {
+        let body = format!(
+            "--{0}\r\nContent-Disposition : form-data; name=\"foo\"\r\n\
+             Content-Type\t: text/plain\r\n\r\nbar\r\n--{0}--\r\n",
+            BOUNDARY
+        );
+
+        for chunk_size in 1..=body.len() {
+            let mut form_data = FormData::new(BOUNDARY);
+            let mut chunks = body.as_bytes().chunks(chunk_size);
+            let mut parts = Vec::new();
+
+            loop {
+                match form_data.read().unwrap() {
+                    Read::NeedsWrite => match chunks.next() {
+                        Some(chunk) => form_data.write(Bytes::copy_from_slice(chunk)).unwrap(),
+                        None => form_data.write_eof(),
+                    },
+                    Read::NewPart { headers, .. } => {
+                        parts.push((headers.parse().unwrap(), Vec::new()))
+                    }
+                    Read::Part(bytes) => parts.last_mut().unwrap().1.extend_from_slice(&bytes),
+                    Read::PartEof | Read::None => {}
+                    Read::Eof => break,
+                }
+            }
+
+            assert_eq!(parts.len(), 1);
+            assert_eq!(parts[0].0.name, "foo");
+            assert_eq!(parts[0].0.content_type.as_deref(), Some("text/plain"));
+            assert_eq!(parts[0].1, b"bar");
+        }
+
+        assert_eq!(trim_header_names(b"a: b\r\n\r\n"), None);
+        assert_eq!(
+            trim_header_names(b"a : b\r\n c : d\r\n\r\n").unwrap(),
+            "a: b\r\n c : d\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn unfold_headers() {
+        let body = format!(
+            "--{0}\r\ncontent-disposition: form-data;\r\n name=\"foo\";\r\n\tfilename=\"bar.txt\"\r\n\
+             content-type: text/plain\r\n\r\nbar\r\n--{0}\r\n\
+             content-disposition: form-data; name=\"abcd\"\r\n\r\nefgh\r\n--{0}--\r\n",
+            BOUNDARY
+        );
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.write(Bytes::from(body.clone())).unwrap();
+        assert!(matches!(read(&mut form_data), Err(Error::Headers { .. })));
+
+        for chunk_size in 1..=body.len() {
+            let mut form_data = FormData::new(BOUNDARY);
+            form_data.set_unfold_headers(true);
+            let mut chunks = body.as_bytes().chunks(chunk_size);
+            let mut parts = Vec::new();
+
+            loop {
+                match form_data.read().unwrap() {
+                    Read::NeedsWrite => match chunks.next() {
+                        Some(chunk) => form_data.write(Bytes::copy_from_slice(chunk)).unwrap(),
+                        None => form_data.write_eof(),
+                    },
+                    Read::NewPart { headers, .. } => {
+                        parts.push((headers.parse().unwrap(), Vec::new()))
+                    }
+                    Read::Part(bytes) => parts.last_mut().unwrap().1.extend_from_slice(&bytes),
+                    Read::PartEof | Read::None => {}
+                    Read::Eof => break,
+                }
+            }
+
+            assert_eq!(parts.len(), 2);
+            assert_eq!(parts[0].0.name, "foo");
+            assert_eq!(parts[0].0.filename.as_deref(), Some("bar.txt"));
+            assert_eq!(parts[0].0.content_type.as_deref(), Some("text/plain"));
+            assert_eq!(parts[0].1, b"bar");
+            assert_eq!(parts[1].0.name, "abcd");
+            assert_eq!(parts[1].1, b"efgh");
+        }
+    }
+
+    #[test]
+    fn strip_bom() {
+        let body = format!(
+            "--{0}\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\n\u{feff}bar\r\n\
+             --{0}\r\ncontent-disposition: form-data; name=\"abcd\"\r\n\
+             content-type: text/plain; charset=utf-8\r\n\r\n\u{feff}\r\n\
+             --{0}\r\ncontent-disposition: form-data; name=\"file\"; filename=\"a.bin\"\r\n\r\n\u{feff}efgh\r\n\
+             --{0}\r\ncontent-disposition: form-data; name=\"baz\"\r\n\r\n\u{fe}\r\n\
+             --{0}--\r\n",
+            BOUNDARY
+        );
+
+        for chunk_size in 1..=body.len() {
+            let parts = decode(FormData::new(BOUNDARY), body.as_bytes(), chunk_size).unwrap();
+            assert_eq!(parts[0], "\u{feff}bar".as_bytes());
+
+            let mut form_data = FormData::new(BOUNDARY);
+            form_data.set_strip_bom(true);
+            let parts = decode(form_data, body.as_bytes(), chunk_size).unwrap();
+            assert_eq!(
+                parts,
+                vec![
+                    b"bar".to_vec(),
+                    b"".to_vec(),
+                    "\u{feff}efgh".as_bytes().to_vec(),
+                    "\u{fe}".as_bytes().to_vec(),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn recycle_headers() {
+        let body = body(&[("foo", "bar"), ("baz", "qux")]);
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.write(body).unwrap();
+        let headers = match read(&mut form_data) {
+            Ok(Read::NewPart { headers, .. }) => headers,
+            read => panic!("unexpected {:?}", read),
+        };
+        assert_eq!(headers.parse().unwrap().name, "foo");
+        form_data.recycle_headers(headers);
+        let ptr = form_data.headers_buf.as_ptr();
+
+        assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == "bar"));
+        assert!(matches!(read(&mut form_data), Ok(Read::PartEof)));
+        match read(&mut form_data) {
+            Ok(Read::NewPart { headers, .. }) => {
+                assert_eq!(headers.parse().unwrap().name, "baz");
+                let headers = headers.into_headers();
+                assert_eq!(headers.as_ptr(), ptr);
+            }
+            read => panic!("unexpected {:?}", read),
+        }
+        assert!(form_data.headers_buf.is_empty());
+    }
+
+    #[test]
+    fn collect_headers() {
+        let body = body(&[("foo", "bar")]);
+        let raw = "content-disposition: form-data; name=\"foo\"\r\n\r\n";
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.write(body.clone()).unwrap();
+        match read(&mut form_data) {
+            Ok(Read::NewPart { headers, .. }) => {
+                assert_eq!(headers.as_bytes(), raw);
+                assert_eq!(headers.parse().unwrap().name, "foo");
+            }
+            read => panic!("unexpected {:?}", read),
+        }
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_collect_headers(false);
+        form_data.write(body).unwrap();
+        match read(&mut form_data) {
+            Ok(Read::NewPart { headers, .. }) => {
+                assert_eq!(headers.as_bytes(), raw);
+                assert_eq!(headers.iter().count(), 0);
+            }
+            read => panic!("unexpected {:?}", read),
+        }
+        assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == "bar"));
+    }
+
+    #[test]
+    fn empty_parts() {
+        /// Decode `body` `chunk_size` bytes at a time, describing every `Read`
+        fn events(body: &str, chunk_size: usize) -> Vec<String> {
+            let mut form_data = FormData::new(BOUNDARY);
+            let mut chunks = body.as_bytes().chunks(chunk_size);
+            let mut events = Vec::new();
+            loop {
+                match form_data.read().unwrap() {
+                    Read::NeedsWrite => match chunks.next() {
+                        Some(chunk) => form_data.write(Bytes::copy_from_slice(chunk)).unwrap(),
+                        None => form_data.write_eof(),
+                    },
+                    Read::NewPart { headers, .. } => {
+                        events.push(headers.name().unwrap().into_owned())
+                    }
+                    Read::Part(bytes) => match events.last_mut() {
+                        Some(last) if last.starts_with('=') => {
+                            last.push_str(std::str::from_utf8(&bytes).unwrap())
+                        }
+                        _ => events.push(format!("={}", std::str::from_utf8(&bytes).unwrap())),
+                    },
+                    Read::PartEof => events.push("PartEof".to_owned()),
+                    Read::None => {}
+                    Read::Eof => return events,
+                }
+            }
+        }
+
+        let headers = "content-disposition: form-data; name=\"foo\"\r\n\r\n";
+        let close = format!("--{}\r\n{}\r\n--{}--\r\n", BOUNDARY, headers, BOUNDARY);
+        let no_close = format!("--{}\r\n{}", BOUNDARY, headers);
+        let multiple = format!(
+            "--{0}\r\n{1}\r\n--{0}\r\n{1}bar\r\n--{0}\r\n{1}\r\n--{0}--\r\n",
+            BOUNDARY, headers
+        );
+        let truncated = format!("--{0}\r\n{1}\r\n--{0}\r\n{1}bar", BOUNDARY, headers);
+
+        for chunk_size in 1..=multiple.len() {
+            assert_eq!(events(&close, chunk_size), ["foo", "PartEof"]);
+            assert_eq!(events(&no_close, chunk_size), ["foo", "PartEof"]);
+            assert_eq!(
+                events(&multiple, chunk_size),
+                ["foo", "PartEof", "foo", "=bar", "PartEof", "foo", "PartEof"]
+            );
+            assert_eq!(
+                events(&truncated, chunk_size),
+                ["foo", "PartEof", "foo", "=bar", "PartEof"]
+            );
+        }
+    }
+
+    #[test]
+    fn preamble() {
+        let body = body(&[("foo", "bar")]);
+        let preambles = [
+            "\r\n".to_owned(),
+            "This is the preamble\r\n".to_owned(),
+            // A boundary which isn't at the start of a line is part of the preamble
+            format!("Not a --{}\r\nboundary\r\n", BOUNDARY),
+            format!("--{}\r\n", &BOUNDARY[..BOUNDARY.len() - 1]),
+        ];
+
+        for preamble in &preambles {
+            let mut with_preamble = preamble.as_bytes().to_vec();
+            with_preamble.extend_from_slice(&body);
+
+            for chunk_size in 1..=with_preamble.len() {
+                for &lenient in &[false, true] {
+                    let mut form_data = FormData::new(BOUNDARY);
+                    form_data.set_lenient(lenient);
+                    let parts = decode(form_data, &with_preamble, chunk_size).unwrap();
+                    assert_eq!(parts, vec![b"bar".to_vec()], "{:?}", preamble);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn epilogue() {
+        let body = body(&[("foo", "bar")]);
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.write(body.clone()).unwrap();
+        while !matches!(form_data.read(), Ok(Read::Eof)) {}
+        let err = form_data.write(Bytes::from_static(b"\r\n")).unwrap_err();
+        assert_eq!(err.kind(), WriteErrorKind::Finished);
+        assert_eq!(err.into_bytes(), "\r\n");
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_discard_epilogue(true);
+        form_data.write(body).unwrap();
+        while !matches!(form_data.read(), Ok(Read::Eof)) {}
+        let buffered_len = form_data.buffered_len();
+        form_data
+            .write(Bytes::from_static(b"epilogue\r\n"))
+            .unwrap();
+        assert_eq!(form_data.buffered_len(), buffered_len);
+        assert!(matches!(form_data.read(), Ok(Read::Eof)));
+
+        form_data.write_eof();
+        let err = form_data.write(Bytes::from_static(b"\r\n")).unwrap_err();
+        assert_eq!(err.kind(), WriteErrorKind::EofWritten);
+    }
+
+    #[test]
+    fn builder() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh"), ("baz", "")]);
+
+        let builder = FormData::builder().max_parts(2).capacity(64);
+        let err = decode(builder.build(BOUNDARY), &body, 7).unwrap_err();
+        assert_eq!(err, Error::TooManyParts { limit: 2 });
+
+        let builder = FormData::builder().max_part_size(4).lenient(true);
+        let lf_body = str::from_utf8(&body).unwrap().replace("\r\n", "\n");
+        let parts = decode(builder.build(BOUNDARY), lf_body.as_bytes(), 7).unwrap();
+        assert_eq!(parts, vec![b"bar".to_vec(), b"efgh".to_vec(), b"".to_vec()]);
+
+        let builder = FormData::builder().max_header_count(0);
+        let err = decode(builder.build(BOUNDARY), &body, 7).unwrap_err();
+        assert!(matches!(err, Error::Headers { .. }));
+    }
+
+    #[test]
+    fn builder_options() {
+        let body = body(&[("foo", "\u{feff}bar")]);
+        let builder = FormData::builder().strip_bom(true);
+        let parts = decode(builder.build(BOUNDARY), &body, 7).unwrap();
+        assert_eq!(parts, vec![b"bar".to_vec()]);
+
+        let form_data = FormData::builder().build(BOUNDARY);
+        assert!(form_data.collect_headers);
+        assert!(!form_data.unfold_headers);
+
+        let form_data = FormData::builder()
+            .max_buffered_len(16)
+            .unfold_headers(true)
+            .strip_bom(true)
+            .discard_epilogue(true)
+            .collect_headers(false)
+            .validate_content_length(true)
+            .build(BOUNDARY);
+        assert_eq!(form_data.max_buffered_len, Some(16));
+        assert!(form_data.unfold_headers);
+        assert!(form_data.strip_bom);
+        assert!(form_data.discard_epilogue);
+        assert!(!form_data.collect_headers);
+        assert!(form_data.validate_content_length);
+        #[cfg(feature = "cte")]
+        {
+            assert!(!form_data.decode_transfer_encoding);
+            let form_data = FormData::builder()
+                .decode_transfer_encoding(true)
+                .build(BOUNDARY);
+            assert!(form_data.decode_transfer_encoding);
+        }
+    }
+
+    #[test]
+    fn with_capacity() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh"), ("baz", "")]);
+
+        for chunk_size in 1..=body.len() {
+            let form_data = FormData::with_capacity(BOUNDARY, 64);
+            let parts = decode(form_data, &body, chunk_size).unwrap();
+            assert_eq!(parts, vec![b"bar".to_vec(), b"efgh".to_vec(), b"".to_vec()]);
+        }
+    }
+
+    #[test]
+    fn queued_writes() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh"), ("baz", "")]);
+
+        for chunk_size in 1..=body.len() {
+            let mut form_data = FormData::new(BOUNDARY);
+            for chunk in body.chunks(chunk_size) {
+                form_data.write(Bytes::copy_from_slice(chunk)).unwrap();
+            }
+            form_data.write_eof();
+
+            let mut parts = Vec::new();
+            loop {
+                match form_data.read().unwrap() {
+                    Read::NewPart { .. } => parts.push(Vec::new()),
+                    Read::Part(bytes) => parts.last_mut().unwrap().extend_from_slice(&bytes),
+                    Read::PartEof | Read::None => {}
+                    Read::NeedsWrite => panic!("everything has already been written"),
+                    Read::Eof => break,
+                }
+            }
+            assert_eq!(parts, vec![b"bar".to_vec(), b"efgh".to_vec(), b"".to_vec()]);
+        }
+    }
+
+    #[test]
+    fn decode_all_parts() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh"), ("baz", "")]);
+
+        let parts = decode_all(BOUNDARY, body).unwrap();
+        let parts = parts
+            .iter()
+            .map(|(headers, body)| (headers.parse().unwrap().name, body.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parts,
+            vec![
+                ("foo".to_owned(), Bytes::from_static(b"bar")),
+                ("abcd".to_owned(), Bytes::from_static(b"efgh")),
+                ("baz".to_owned(), Bytes::new()),
+            ]
+        );
+
+        assert_eq!(
+            decode_all(BOUNDARY, Bytes::from(format!("--{}??", BOUNDARY))).unwrap_err(),
+            Error::UnexpectedBoundarySuffix {
+                offset: BOUNDARY.len() as u64 + 2
+            }
+        );
+    }
+
+    #[test]
+    fn transport_padding() {
+        let body = format!(
+            "--{0} \t \r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n--{0}\t\r\n\
+             content-disposition: form-data; name=\"abcd\"\r\n\r\n \r\n--{0}--  \r\n",
+            BOUNDARY
+        );
+
+        for chunk_size in 1..=body.len() {
+            let parts = decode(FormData::new(BOUNDARY), body.as_bytes(), chunk_size).unwrap();
+            assert_eq!(parts, vec![b"bar".to_vec(), b" ".to_vec()]);
+        }
+    }
+
+    #[test]
+    fn false_boundaries() {
+        let contents = format!("a\r\n--{0}b\r\n--{0} x\n--{0}?", BOUNDARY);
+        let body = format!(
+            "--{0}\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\n{1}\r\n--{0}--\r\n",
+            BOUNDARY, contents
+        );
+
+        for &lenient in &[false, true] {
+            for chunk_size in 1..body.len() {
+                let mut form_data = FormData::new(BOUNDARY);
+                form_data.set_lenient(lenient);
+                form_data.set_allow_false_boundaries(true);
+
+                let parts = decode(form_data, body.as_bytes(), chunk_size).unwrap();
+                assert_eq!(parts, vec![contents.as_bytes().to_vec()]);
+            }
+        }
+
+        assert!(matches!(
+            decode(FormData::new(BOUNDARY), body.as_bytes(), body.len()),
+            Err(Error::UnexpectedBoundarySuffix { .. })
+        ));
+    }
+
+    #[test]
+    fn false_first_boundaries() {
+        let part = format!(
+            "--{0}\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n--{0}--\r\n",
+            BOUNDARY
+        );
+
+        for preamble in &[
+            format!("--{}XY\r\n", BOUNDARY),
+            format!("abc\r\n--{}XY\r\n", BOUNDARY),
+        ] {
+            let body = format!("{}{}", preamble, part);
+
+            for &lenient in &[false, true] {
+                for chunk_size in 1..body.len() {
+                    let mut form_data = FormData::new(BOUNDARY);
+                    form_data.set_lenient(lenient);
+                    form_data.set_allow_false_boundaries(true);
+
+                    let parts = decode(form_data, body.as_bytes(), chunk_size).unwrap();
+                    assert_eq!(parts, vec![b"bar".to_vec()]);
+                }
+            }
+
+            assert!(matches!(
+                decode(FormData::new(BOUNDARY), body.as_bytes(), body.len()),
+                Err(Error::UnexpectedBoundarySuffix { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn lenient_line_feeds() {
+        let body = format!(
+            "--{0}\ncontent-disposition: form-data; name=\"foo\"\n\nbar\r\n\n--{0}\n\
+             content-disposition: form-data; name=\"abcd\"\n\n\r\n--{0}--\n",
+            BOUNDARY
+        );
+
+        for chunk_size in 1..body.len() {
+            let mut form_data = FormData::new(BOUNDARY);
+            form_data.set_lenient(true);
+
+            let parts = decode(form_data, body.as_bytes(), chunk_size).unwrap();
+            assert_eq!(parts, vec![b"bar\r\n".to_vec(), b"".to_vec()]);
+        }
+
+        assert_eq!(
+            decode(FormData::new(BOUNDARY), body.as_bytes(), body.len()),
+            Err(Error::UnexpectedBoundarySuffix {
+                offset: BOUNDARY.len() as u64 + 2
+            })
+        );
+    }
+
+    #[test]
+    fn lenient_carriage_returns() {
+        let body = body(&[("foo", "bar\r"), ("abcd", "\n\r\n"), ("baz", "")]);
+
+        for chunk_size in 1..body.len() {
+            let strict = decode(FormData::new(BOUNDARY), &body, chunk_size).unwrap();
+
+            let mut form_data = FormData::new(BOUNDARY);
+            form_data.set_lenient(true);
+            let lenient = decode(form_data, &body, chunk_size).unwrap();
+
+            assert_eq!(strict, lenient);
+            assert_eq!(
+                lenient,
+                vec![b"bar\r".to_vec(), b"\n\r\n".to_vec(), b"".to_vec()]
+            );
+        }
+    }
+
+    #[test]
+    fn max_part_size() {
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_max_part_size(4);
+        form_data
+            .write(body(&[
+                ("foo", "bar"),
+                ("abcd", "efghijkl"),
+                ("baz", "abcd"),
+            ]))
+            .unwrap();
+
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == "bar"));
+        assert!(matches!(read(&mut form_data), Ok(Read::PartEof)));
+
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert!(matches!(
+            read(&mut form_data),
+            Err(Error::PartTooLarge { limit: 4 })
+        ));
+        assert!(matches!(read(&mut form_data), Ok(Read::PartEof)));
+
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == "abcd"));
+        assert!(matches!(read(&mut form_data), Ok(Read::PartEof)));
+        assert!(matches!(read(&mut form_data), Ok(Read::Eof)));
+    }
+
+    #[test]
+    fn max_total_size() {
+        let body = body(&[("foo", "bar"), ("abcd", "efgh")]);
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_max_total_size(body.len());
+        form_data.write(body.clone()).unwrap();
+
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == "bar"));
+        assert!(matches!(read(&mut form_data), Ok(Read::PartEof)));
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == "efgh"));
+        assert!(matches!(read(&mut form_data), Ok(Read::PartEof)));
+        assert!(matches!(read(&mut form_data), Ok(Read::Eof)));
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_max_total_size(90);
+        form_data.write(body).unwrap();
+
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == "bar"));
+        assert!(matches!(read(&mut form_data), Ok(Read::PartEof)));
+        assert!(matches!(
+            read(&mut form_data),
+            Err(Error::TotalSizeExceeded { limit: 90 })
+        ));
+    }
+
+    #[test]
+    fn max_total_size_unterminated_headers() {
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_max_total_size(100);
+        form_data
+            .write(Bytes::from(format!("--{}\r\n", BOUNDARY)))
+            .unwrap();
+
+        let mut result = Ok(Read::NeedsWrite);
+        for _ in 0..100 {
+            form_data.write_slice(b"x-header: value\r\n").unwrap();
+            result = form_data.read();
+            while let Ok(Read::None) = result {
+                result = form_data.read();
+            }
+            if !matches!(result, Ok(Read::NeedsWrite)) {
+                break;
+            }
+        }
+
+        assert!(matches!(
+            result,
+            Err(Error::TotalSizeExceeded { limit: 100 })
+        ));
+        assert!(form_data.buffered_len() < 200);
+    }
+
+    #[test]
+    fn max_parts() {
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_max_parts(2);
+        form_data
+            .write(body(&[("foo", "bar"), ("abcd", "efgh"), ("baz", "abcd")]))
+            .unwrap();
+
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == "bar"));
+        assert!(matches!(read(&mut form_data), Ok(Read::PartEof)));
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == "efgh"));
+        assert!(matches!(read(&mut form_data), Ok(Read::PartEof)));
+        assert!(matches!(
+            read(&mut form_data),
+            Err(Error::TooManyParts { limit: 2 })
+        ));
+    }
+
+    #[test]
+    fn max_parts_byte_at_a_time() {
+        let body = body(&[("foo", "bar")]);
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_max_parts(1);
+
+        let mut i = 0;
+        let mut new_parts = 0;
+        loop {
+            match form_data.read().unwrap() {
+                Read::NeedsWrite if i < body.len() => {
+                    form_data.write(body.slice(i..i + 1)).unwrap();
+                    i += 1;
+                }
+                Read::NeedsWrite => form_data.write_eof(),
+                Read::NewPart { .. } => new_parts += 1,
+                Read::Eof => break,
+                _ => {}
+            }
+        }
+        assert_eq!(new_parts, 1);
+    }
+
+    #[test]
+    fn max_header_size() {
+        let contents = "a".repeat(1024);
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_max_header_size(64);
+        form_data.write(body(&[("foo", &contents)])).unwrap();
+
+        assert!(matches!(read(&mut form_data), Ok(Read::NewPart { .. })));
+        assert!(matches!(read(&mut form_data), Ok(Read::Part(bytes)) if bytes == contents));
+        assert!(matches!(read(&mut form_data), Ok(Read::PartEof)));
+        assert!(matches!(read(&mut form_data), Ok(Read::Eof)));
+
+        let mut form_data = FormData::new(BOUNDARY);
+        form_data.set_max_header_size(16);
+        form_data.write(body(&[("foo", &contents)])).unwrap();
+
+        assert!(matches!(
+            read(&mut form_data),
+            Err(Error::HeadersTooLarge { limit: 16 })
+        ));
     }
 }
@@ -0,0 +1,134 @@
+//! `tokio-util` [`Decoder`] multipart decoder.
+//!
+//! ```rust
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use futures_util::stream::TryStreamExt;
+//! use multiparty::server::tokio_util::{Frame, MultipartCodec};
+//! use tokio_util::codec::FramedRead;
+//!
+//! # if false {
+//! let boundary = todo!("A multipart/form-data boundary");
+//! let reader = todo!("A tokio::io::AsyncRead");
+//! # }
+//! # let boundary = "abcd";
+//! # let reader = &b"--abcd\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar\r\n--abcd--"[..];
+//! let mut frames = FramedRead::new(reader, MultipartCodec::new(boundary));
+//!
+//! while let Some(frame) = frames.try_next().await? {
+//!     match frame {
+//!         Frame::NewPart { headers } => println!("name: {:?}", headers.parse()?.name),
+//!         Frame::Part(bytes) => println!("Read {} bytes from the current part", bytes.len()),
+//!         Frame::PartEof => println!("Reached the end of this part"),
+//!         Frame::Eof => println!("Reached the end of the multipart stream"),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Decoder`]: ::tokio_util::codec::Decoder
+
+use std::io::{self, Error};
+
+use ::tokio_util::codec::Decoder;
+use bytes::{Bytes, BytesMut};
+
+use super::sans_io::{self, Read};
+use crate::headers::RawHeaders;
+
+/// A frame decoded by [`MultipartCodec`].
+///
+/// Mirrors [`sans_io::Read`], without the variants which
+/// are handled by the codec.
+#[derive(Debug)]
+pub enum Frame {
+    /// The beginning of a new part.
+    NewPart {
+        /// The headers inside the new part
+        headers: RawHeaders,
+    },
+    /// [`Bytes`] from the current part.
+    Part(Bytes),
+    /// The current part has ended.
+    PartEof,
+    /// The multipart stream has reached its end. No more frames are decoded after this one.
+    Eof,
+}
+
+/// A multipart/form-data [`Decoder`] driving a [`sans_io::FormData`].
+///
+/// [`Decoder`]: ::tokio_util::codec::Decoder
+#[derive(Debug)]
+pub struct MultipartCodec {
+    inner: sans_io::FormData,
+    done: bool,
+}
+
+impl MultipartCodec {
+    /// Construct a new `MultipartCodec` with a boundary of `boundary`.
+    pub fn new(boundary: &str) -> Self {
+        Self::from_sans_io(sans_io::FormData::new(boundary))
+    }
+
+    /// Construct a new `MultipartCodec` from an already configured [`sans_io::FormData`].
+    pub fn from_sans_io(inner: sans_io::FormData) -> Self {
+        Self { inner, done: false }
+    }
+
+    fn read(&mut self, src: &mut BytesMut) -> io::Result<Option<Frame>> {
+        if self.done {
+            // Ignore the epilogue
+            src.clear();
+            return Ok(None);
+        }
+
+        loop {
+            match self.inner.read() {
+                Ok(Read::NeedsWrite) => {
+                    if src.is_empty() {
+                        return Ok(None);
+                    }
+
+                    let bytes = src.split().freeze();
+                    self.inner.write(bytes).expect("we've been told to write");
+                }
+                Ok(Read::None) => {
+                    // continue
+                }
+                Ok(Read::NewPart { headers, .. }) => return Ok(Some(Frame::NewPart { headers })),
+                Ok(Read::Part(bytes)) => return Ok(Some(Frame::Part(bytes))),
+                Ok(Read::PartEof) => return Ok(Some(Frame::PartEof)),
+                Ok(Read::Eof) => {
+                    self.done = true;
+                    src.clear();
+                    return Ok(Some(Frame::Eof));
+                }
+                Err(err) => return Err(Error::other(err)),
+            }
+        }
+    }
+}
+
+impl Decoder for MultipartCodec {
+    type Item = Frame;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        self.read(src)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        if !self.done {
+            if !src.is_empty() {
+                let bytes = src.split().freeze();
+                self.inner
+                    .write(bytes)
+                    .expect("eof hasn't been written yet");
+            }
+            self.inner.write_eof();
+        }
+
+        self.read(src)
+    }
+}
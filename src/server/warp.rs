@@ -0,0 +1,164 @@
+//! [warp] filter for `multipart/form-data` request bodies.
+//!
+//! Targets warp 0.3.
+//!
+//! ```rust
+//! use futures_util::stream::TryStreamExt;
+//! use multiparty::server::warp::{form_data, Multipart};
+//! use warp::Filter;
+//!
+//! async fn handler(mut multipart: Multipart) -> Result<String, warp::Rejection> {
+//!     let mut names = Vec::new();
+//!     while let Some(part) = multipart.try_next().await.map_err(|_| warp::reject())? {
+//!         if let Ok(headers) = part.raw_headers().parse() {
+//!             names.push(headers.name);
+//!         }
+//!     }
+//!
+//!     Ok(names.join(", "))
+//! }
+//!
+//! let route = warp::post().and(form_data()).and_then(handler);
+//! # drop(route);
+//! ```
+//!
+//! [warp]: https://docs.rs/warp
+
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::{Buf, Bytes};
+use futures_core::Stream;
+use pin_project_lite::pin_project;
+use warp::reject::{Reject, Rejection};
+use warp::Filter;
+
+use super::owned_futures03::{FormData, FormDataBuilder};
+use crate::boundary::from_content_type;
+
+/// The limit on the size of the multipart stream used by [`form_data`],
+/// the same one `warp::multipart::form` uses.
+const DEFAULT_MAX_TOTAL_SIZE: usize = 2 * 1024 * 1024;
+
+/// A `multipart/form-data` request body.
+///
+/// Extracted by the [`form_data`] filter.
+pub type Multipart = FormData<BodyStream>;
+
+/// The body of a request, as a `Stream` of [`Bytes`].
+pub struct BodyStream {
+    inner: Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Send>>,
+}
+
+pin_project! {
+    /// Adapter turning the `Buf`s yielded by warp into [`Bytes`]
+    struct IntoBytes<S> {
+        #[pin]
+        stream: S,
+    }
+}
+
+/// Rejection returned when [`Multipart`] can't be extracted from a request.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum MultipartRejection {
+    /// The request doesn't have a `Content-Type` header.
+    MissingContentType,
+    /// The `Content-Type` of the request isn't `multipart/*` with a `boundary`.
+    InvalidContentType,
+}
+
+/// A `Filter` extracting [`Multipart`] from the request body.
+///
+/// The boundary is taken from the `Content-Type` header of the request.
+/// Requests without a valid one are rejected with [`MultipartRejection`].
+///
+/// Multipart streams bigger than 2 MB make [`Multipart`] yield an error,
+/// like with `warp::multipart::form`. Use [`form_data_with`] to change the limits.
+pub fn form_data() -> impl Filter<Extract = (Multipart,), Error = Rejection> + Clone {
+    form_data_with(FormDataBuilder::new().max_total_size(DEFAULT_MAX_TOTAL_SIZE))
+}
+
+/// A `Filter` extracting [`Multipart`] from the request body, configured by `builder`.
+///
+/// Unlike [`form_data`], no limit is applied unless it's set on `builder`.
+///
+/// ```rust
+/// use multiparty::server::owned_futures03::FormDataBuilder;
+/// use multiparty::server::warp::form_data_with;
+///
+/// let filter = form_data_with(
+///     FormDataBuilder::new()
+///         .max_total_size(16 * 1024 * 1024)
+///         .max_parts(16),
+/// );
+/// # drop(filter);
+/// ```
+pub fn form_data_with(
+    builder: FormDataBuilder,
+) -> impl Filter<Extract = (Multipart,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("content-type")
+        .and_then(|content_type: Option<String>| async move {
+            let content_type = content_type
+                .ok_or_else(|| warp::reject::custom(MultipartRejection::MissingContentType))?;
+            from_content_type(&content_type)
+                .map(str::to_owned)
+                .ok_or_else(|| warp::reject::custom(MultipartRejection::InvalidContentType))
+        })
+        .and(warp::body::stream())
+        .map(move |boundary: String, stream| {
+            let stream = BodyStream {
+                inner: Box::pin(IntoBytes { stream }),
+            };
+            builder.build(stream, &boundary)
+        })
+}
+
+impl Stream for BodyStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+impl<S, B> Stream for IntoBytes<S>
+where
+    S: Stream<Item = Result<B, warp::Error>>,
+    B: Buf,
+{
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.project().stream.poll_next(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Some(Ok(mut buf))) => {
+                Poll::Ready(Some(Ok(buf.copy_to_bytes(buf.remaining()))))
+            }
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(io::Error::other(err)))),
+            Poll::Ready(None) => Poll::Ready(None),
+        }
+    }
+}
+
+impl Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BodyStream").finish()
+    }
+}
+
+impl Display for MultipartRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingContentType => f.write_str("missing Content-Type header"),
+            Self::InvalidContentType => f.write_str("Content-Type isn't multipart with a boundary"),
+        }
+    }
+}
+
+impl StdError for MultipartRejection {}
+
+impl Reject for MultipartRejection {}
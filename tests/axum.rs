@@ -0,0 +1,119 @@
+#[cfg(all(feature = "server", feature = "axum"))]
+use axum_core::body::Body;
+#[cfg(all(feature = "server", feature = "axum"))]
+use axum_core::extract::{FromRequest, Request};
+#[cfg(all(feature = "server", feature = "axum"))]
+use axum_core::response::IntoResponse;
+#[cfg(all(feature = "server", feature = "axum"))]
+use futures_util::stream::TryStreamExt;
+#[cfg(all(feature = "server", feature = "axum"))]
+use http::StatusCode;
+
+#[cfg(all(feature = "server", feature = "axum"))]
+use multiparty::server::axum::{Multipart, MultipartRejection};
+#[cfg(all(feature = "server", feature = "axum"))]
+use multiparty::server::owned_futures03::FormDataBuilder;
+
+#[cfg(all(feature = "server", feature = "axum"))]
+#[tokio::test]
+async fn extract() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    let req = Request::builder()
+        .header(
+            "content-type",
+            format!("multipart/form-data; boundary=\"{}\"", boundary),
+        )
+        .body(Body::from(body))
+        .unwrap();
+    let mut multipart = Multipart::from_request(req, &()).await.unwrap();
+
+    let mut part1 = multipart.try_next().await.unwrap().unwrap();
+    assert_eq!(part1.raw_headers().parse().unwrap().name, "foo");
+    assert_eq!(part1.read_to_end().await.unwrap(), "bar");
+
+    assert!(multipart.try_next().await.unwrap().is_none());
+}
+
+#[cfg(all(feature = "server", feature = "axum"))]
+#[tokio::test]
+async fn reject() {
+    let req = Request::builder().body(Body::empty()).unwrap();
+    let rejection = Multipart::from_request(req, &()).await.unwrap_err();
+    assert!(matches!(rejection, MultipartRejection::MissingContentType));
+    assert_eq!(rejection.into_response().status(), StatusCode::BAD_REQUEST);
+
+    let req = Request::builder()
+        .header("content-type", "application/json")
+        .body(Body::empty())
+        .unwrap();
+    let rejection = Multipart::from_request(req, &()).await.unwrap_err();
+    assert!(matches!(rejection, MultipartRejection::InvalidContentType));
+}
+
+#[cfg(all(feature = "server", feature = "axum"))]
+#[tokio::test]
+async fn builder_extension() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    let mut req = Request::builder()
+        .header(
+            "content-type",
+            format!("multipart/form-data; boundary=\"{}\"", boundary),
+        )
+        .body(Body::from(body))
+        .unwrap();
+    req.extensions_mut()
+        .insert(FormDataBuilder::new().max_part_size(2));
+    let mut multipart = Multipart::from_request(req, &()).await.unwrap();
+
+    let mut part1 = multipart.try_next().await.unwrap().unwrap();
+    assert!(part1.read_to_end().await.is_err());
+}
+
+#[cfg(all(feature = "server", feature = "axum"))]
+#[tokio::test]
+async fn default_body_limit() {
+    let boundary = "--abcdef1234--";
+    // Over the 2 MB axum applies by default
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         {1}\r\n\
+         --{0}--\r\n\
+         ",
+        boundary,
+        "a".repeat(3 * 1024 * 1024)
+    );
+
+    let req = Request::builder()
+        .header(
+            "content-type",
+            format!("multipart/form-data; boundary=\"{}\"", boundary),
+        )
+        .body(Body::from(body))
+        .unwrap();
+    let mut multipart = Multipart::from_request(req, &()).await.unwrap();
+
+    // The body declares its length upfront, so it's rejected before any part is yielded
+    assert!(multipart.try_next().await.is_err());
+}
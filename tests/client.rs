@@ -0,0 +1,99 @@
+#[cfg(all(feature = "server", feature = "client", feature = "futures03"))]
+use bytes::Bytes;
+#[cfg(all(feature = "server", feature = "client", feature = "futures03"))]
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+
+#[cfg(all(feature = "server", feature = "client", feature = "futures03"))]
+use multiparty::client::encoder::MultipartEncoder;
+#[cfg(all(feature = "server", feature = "client", feature = "futures03"))]
+use multiparty::server::owned_futures03::FormData;
+
+#[cfg(all(feature = "server", feature = "client", feature = "futures03"))]
+#[tokio::test]
+async fn encode() {
+    let mut encoder = MultipartEncoder::with_boundary("abcd");
+    encoder.add_bytes("foo", None, None, "bar");
+    encoder.add_bytes("abcd", Some("test.txt"), Some("text/plain"), "efgh");
+    assert_eq!(encoder.content_type(), "multipart/form-data; boundary=abcd");
+
+    let body = encoder.into_stream().collect::<Vec<Bytes>>().await.concat();
+    assert_eq!(
+        body,
+        &b"--abcd\r\n\
+           content-disposition: form-data; name=\"foo\"\r\n\r\n\
+           bar\r\n\
+           --abcd\r\n\
+           content-disposition: form-data; name=\"abcd\"; filename=\"test.txt\"\r\n\
+           content-type: text/plain\r\n\r\n\
+           efgh\r\n\
+           --abcd--\r\n"[..]
+    );
+}
+
+#[cfg(all(feature = "server", feature = "client", feature = "futures03"))]
+#[tokio::test]
+async fn quoted_boundary() {
+    let mut encoder = MultipartEncoder::with_boundary("ab cd");
+    encoder.add_bytes("foo", None, Some("text/plain\r\nx-foo: bar"), "baz");
+    let content_type = encoder.content_type();
+    let boundary = multiparty::boundary::from_content_type(&content_type).unwrap();
+    assert_eq!(boundary, "ab cd");
+
+    let stream = encoder.into_stream().map(Ok::<_, std::io::Error>);
+    let mut parts = FormData::new(stream, boundary);
+    let mut part = parts.try_next().await.unwrap().unwrap();
+    let headers = part.raw_headers();
+    assert!(headers.get("x-foo").is_none());
+    assert_eq!(
+        headers.get_str("content-type").unwrap().unwrap(),
+        "text/plain%0D%0Ax-foo: bar"
+    );
+    assert_eq!(part.read_to_end().await.unwrap(), "baz");
+    assert!(parts.try_next().await.unwrap().is_none());
+}
+
+#[cfg(all(feature = "server", feature = "client", feature = "futures03"))]
+#[tokio::test]
+async fn encode_empty() {
+    let encoder = MultipartEncoder::with_boundary("abcd");
+
+    let body = encoder.into_stream().collect::<Vec<Bytes>>().await.concat();
+    assert_eq!(body, &b"--abcd--\r\n"[..]);
+}
+
+#[cfg(all(feature = "server", feature = "client", feature = "futures03"))]
+#[tokio::test]
+async fn roundtrip() {
+    let body_contents = (0..4096)
+        .map(|_| fastrand::alphanumeric())
+        .collect::<String>();
+    let chunks = body_contents
+        .as_bytes()
+        .chunks(100)
+        .map(Bytes::copy_from_slice)
+        .collect::<Vec<_>>();
+
+    let mut encoder = MultipartEncoder::new();
+    encoder.add_bytes("foo", None, None, "bar");
+    encoder.add_stream("abcd", Some("test.txt"), None, stream::iter(chunks));
+    let boundary = encoder.boundary().to_owned();
+
+    let stream = encoder.into_stream().map(Ok::<_, std::io::Error>);
+    let mut parts = FormData::new(stream, &boundary);
+
+    let part1 = parts.try_next().await.unwrap().unwrap();
+    let headers1 = part1.raw_headers().parse().unwrap();
+    assert_eq!(headers1.name, "foo");
+    assert!(headers1.filename.is_none());
+    let bytes1 = part1.try_collect::<Vec<Bytes>>().await.unwrap().concat();
+    assert_eq!(bytes1, b"bar");
+
+    let part2 = parts.try_next().await.unwrap().unwrap();
+    let headers2 = part2.raw_headers().parse().unwrap();
+    assert_eq!(headers2.name, "abcd");
+    assert_eq!(headers2.filename.as_deref(), Some("test.txt"));
+    let bytes2 = part2.try_collect::<Vec<Bytes>>().await.unwrap().concat();
+    assert_eq!(bytes2, body_contents.as_bytes());
+
+    assert!(parts.try_next().await.unwrap().is_none());
+}
@@ -3,7 +3,7 @@ use std::future::Future;
 
 #[cfg(all(feature = "server", feature = "futures03"))]
 use bytes::{BufMut, Bytes, BytesMut};
-use futures_core::FusedStream;
+use futures_core::{FusedStream, Stream};
 #[cfg(all(feature = "server", feature = "futures03"))]
 use futures_util::stream::{self, StreamExt, TryStreamExt};
 #[cfg(all(feature = "server", feature = "futures03"))]
@@ -40,6 +40,45 @@ async fn empty() {
     }
 }
 
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn empty_saw_no_boundary() {
+    let boundary = "--abcdef1234--";
+
+    let s = stream::empty();
+    let mut parts = FormData::new(s, boundary);
+
+    assert!(parts.next().await.is_none());
+    assert!(!parts.saw_any_boundary());
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn boundary() {
+    let boundary = "--abcdef1234--";
+
+    let s = stream::empty();
+    let mut parts = FormData::new(s, boundary);
+    assert_eq!(parts.boundary(), boundary);
+
+    assert!(parts.next().await.is_none());
+    assert_eq!(parts.boundary(), boundary);
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn no_parts() {
+    let boundary = "--abcdef1234--";
+
+    let s = stream::iter(vec![Ok(Bytes::from(format!("--{}--\r\n", boundary)))]);
+    let mut parts = FormData::new(s, boundary);
+
+    assert!(parts.next().await.is_none());
+    assert!(parts.is_terminated());
+    assert!(parts.saw_any_boundary());
+    assert!(!parts.was_truncated());
+}
+
 #[cfg(all(feature = "server", feature = "futures03"))]
 #[tokio::test]
 async fn empty_bytes() {
@@ -115,6 +154,7 @@ async fn bytes_bad_suffix() {
         boundary
     );
 
+    let offset = body.find("??").unwrap() as u64;
     let s = stream::once(ready_yield_now_maybe(Ok(Bytes::from(body))));
     let mut parts = FormData::new(s, boundary);
 
@@ -136,9 +176,10 @@ async fn bytes_bad_suffix() {
     }
 
     {
+        let err = parts.next().await.unwrap().unwrap_err();
         assert_eq!(
-            parts.next().await.unwrap().unwrap_err().to_string(),
-            Error::UnexpectedBoundarySuffix.to_string()
+            Error::from_io_error(&err),
+            Some(&Error::UnexpectedBoundarySuffix { offset })
         );
     }
 }
@@ -165,7 +206,11 @@ async fn bytes_bad_headers() {
     {
         assert_eq!(
             parts.next().await.unwrap().unwrap_err().to_string(),
-            Error::Headers(httparse::Error::HeaderName).to_string()
+            Error::Headers {
+                error: httparse::Error::HeaderName,
+                offset: boundary.len() as u64 + 4,
+            }
+            .to_string()
         );
     }
 }
@@ -480,6 +525,29 @@ async fn bytes_no_close() {
     }
 }
 
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn bytes_no_close_truncated() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\
+         ",
+        boundary
+    );
+
+    let s = stream::once(ready_yield_now_maybe(Ok(Bytes::from(body))));
+    let mut parts = FormData::new(s, boundary);
+    assert!(!parts.was_truncated());
+
+    let mut part1 = parts.next().await.unwrap().unwrap();
+    assert_eq!(part1.read_to_end().await.unwrap(), "bar");
+    assert!(parts.next().await.is_none());
+    assert!(parts.was_truncated());
+}
+
 #[cfg(all(feature = "server", feature = "futures03"))]
 #[tokio::test]
 async fn bytes_no_close_multipart() {
@@ -631,6 +699,7 @@ async fn byte_at_a_time_random_body() {
 
 #[cfg(all(feature = "server", feature = "futures03"))]
 #[tokio::test]
+#[allow(clippy::map_flatten)]
 async fn byte_at_a_time_spurious_empty() {
     let boundary = "--abcdef1234--";
     let body = format!(
@@ -780,3 +849,871 @@ async fn many_bytes_at_a_time_random_body_no_close() {
         assert!(parts.is_terminated());
     }
 }
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn bytes_too_many_headers() {
+    let boundary = "--abcdef1234--";
+    let extra_headers = (0..10)
+        .map(|i| format!("x-extra-{}: {}\r\n", i, i))
+        .collect::<String>();
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\
+         {1}\r\n\
+         bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary, extra_headers
+    );
+
+    let s = stream::once(ready_yield_now_maybe(Ok(Bytes::from(body))));
+    let mut parts = FormData::with_max_headers(s, boundary, 8);
+
+    assert!(!parts.is_terminated());
+
+    {
+        assert_eq!(
+            parts.next().await.unwrap().unwrap_err().to_string(),
+            Error::Headers {
+                error: httparse::Error::TooManyHeaders,
+                offset: boundary.len() as u64 + 4,
+            }
+            .to_string()
+        );
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn bytes_max_headers() {
+    let boundary = "--abcdef1234--";
+    let extra_headers = (0..10)
+        .map(|i| format!("x-extra-{}: {}\r\n", i, i))
+        .collect::<String>();
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\
+         {1}\r\n\
+         bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary, extra_headers
+    );
+
+    let s = stream::once(ready_yield_now_maybe(Ok(Bytes::from(body))));
+    let mut parts = FormData::with_max_headers(s, boundary, 16);
+
+    assert!(!parts.is_terminated());
+
+    {
+        let mut part1 = parts.next().await.unwrap().unwrap();
+        let headers1 = part1.raw_headers().parse().unwrap();
+        assert_eq!(headers1.name, "foo");
+        assert!(headers1.filename.is_none());
+        assert!(headers1.content_type.is_none());
+
+        assert!(!part1.is_terminated());
+        let bytes1 = part1.next().await.unwrap().unwrap();
+        assert_eq!(bytes1, "bar".as_bytes());
+
+        assert!(part1.next().await.is_none());
+        assert!(part1.is_terminated());
+    }
+
+    {
+        assert!(parts.next().await.is_none());
+        assert!(parts.is_terminated());
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn bytes_many_headers() {
+    let boundary = "--abcdef1234--";
+    let extra_headers = (0..100)
+        .map(|i| format!("x-extra-{}: {}\r\n", i, i))
+        .collect::<String>();
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\
+         {1}\r\n\
+         bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary, extra_headers
+    );
+
+    let s = stream::once(ready_yield_now_maybe(Ok(Bytes::from(body))));
+    let mut parts = FormData::new(s, boundary);
+
+    assert!(!parts.is_terminated());
+
+    {
+        let mut part1 = parts.next().await.unwrap().unwrap();
+        let headers1 = part1.raw_headers().parse().unwrap();
+        assert_eq!(headers1.name, "foo");
+        assert!(headers1.filename.is_none());
+        assert!(headers1.content_type.is_none());
+
+        assert!(!part1.is_terminated());
+        let bytes1 = part1.next().await.unwrap().unwrap();
+        assert_eq!(bytes1, "bar".as_bytes());
+
+        assert!(part1.next().await.is_none());
+        assert!(part1.is_terminated());
+    }
+
+    {
+        assert!(parts.next().await.is_none());
+        assert!(parts.is_terminated());
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn bytes_part_too_large() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"abcd\"\r\n\r\n\
+         efghijkl\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    let s = stream::once(ready_yield_now_maybe(Ok(Bytes::from(body))));
+    let mut parts = FormData::new(s, boundary);
+    parts.set_max_part_size(4);
+
+    assert!(!parts.is_terminated());
+
+    {
+        let mut part1 = parts.next().await.unwrap().unwrap();
+        let bytes1 = part1.next().await.unwrap().unwrap();
+        assert_eq!(bytes1, "bar".as_bytes());
+
+        assert!(part1.next().await.is_none());
+        assert!(part1.is_terminated());
+    }
+
+    {
+        let mut part2 = parts.next().await.unwrap().unwrap();
+        assert_eq!(
+            part2.next().await.unwrap().unwrap_err().to_string(),
+            Error::PartTooLarge { limit: 4 }.to_string()
+        );
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn bytes_read() {
+    let body_contents = (0..4096)
+        .map(|_| fastrand::alphanumeric())
+        .collect::<String>();
+
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         {1}\r\n\
+         --{0}--\r\n\
+         ",
+        boundary, body_contents
+    );
+
+    let chunks = body
+        .as_bytes()
+        .chunks(100)
+        .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+        .collect::<Vec<_>>();
+    let s = stream::iter(chunks);
+    let mut parts = FormData::new(s, boundary);
+
+    {
+        let mut part1 = parts.next().await.unwrap().unwrap();
+        assert_eq!(part1.bytes_read(), 0);
+
+        let mut read = 0;
+        while let Some(bytes) = part1.try_next().await.unwrap() {
+            read += bytes.len() as u64;
+            assert_eq!(part1.bytes_read(), read);
+        }
+        assert_eq!(part1.bytes_read(), body_contents.len() as u64);
+    }
+
+    {
+        assert!(parts.next().await.is_none());
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn into_fields() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"file\"; filename=\"test.txt\"\r\n\r\n\
+         ijkl\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"abcd\"\r\n\r\n\
+         efgh\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    let chunks = body
+        .as_bytes()
+        .chunks(7)
+        .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+        .collect::<Vec<_>>();
+    let s = stream::iter(chunks);
+    let parts = FormData::new(s, boundary);
+
+    let fields = parts.into_fields().await.unwrap();
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields["foo"], "bar");
+    assert_eq!(fields["abcd"], "efgh");
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn read_to_end() {
+    let body_contents = (0..4096)
+        .map(|_| fastrand::alphanumeric())
+        .collect::<String>();
+
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         {1}\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"abcd\"\r\n\r\n\
+         \r\n\
+         --{0}--\r\n\
+         ",
+        boundary, body_contents
+    );
+
+    for &chunk_size in &[7, 100, body.len()] {
+        let chunks = body
+            .as_bytes()
+            .chunks(chunk_size)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>();
+        let s = stream::iter(chunks);
+        let mut parts = FormData::new(s, boundary);
+
+        {
+            let mut part1 = parts.next().await.unwrap().unwrap();
+            assert_eq!(part1.read_to_end().await.unwrap(), body_contents);
+            assert!(part1.is_terminated());
+        }
+
+        {
+            let mut part2 = parts.next().await.unwrap().unwrap();
+            assert_eq!(part2.read_to_end().await.unwrap(), "");
+            assert!(part2.is_terminated());
+        }
+
+        {
+            assert!(parts.next().await.is_none());
+        }
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn parts() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"abcd\"; filename=\"test.txt\"\r\n\r\n\
+         efgh\r\n\
+         --{0}\r\n\
+         content-type: text/plain\r\n\r\n\
+         ijkl\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    let s = stream::once(ready_yield_now_maybe(Ok(Bytes::from(body))));
+    let mut parts = FormData::new(s, boundary).parts();
+
+    {
+        let (headers1, mut part1) = parts.next().await.unwrap().unwrap();
+        assert_eq!(headers1.name, "foo");
+        assert!(headers1.filename.is_none());
+        assert_eq!(part1.read_to_end().await.unwrap(), "bar");
+    }
+
+    {
+        let (headers2, mut part2) = parts.next().await.unwrap().unwrap();
+        assert_eq!(headers2.name, "abcd");
+        assert_eq!(headers2.filename.as_deref(), Some("test.txt"));
+        assert_eq!(part2.read_to_end().await.unwrap(), "efgh");
+    }
+
+    {
+        assert!(parts.next().await.unwrap().is_err());
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn not_unpin_stream() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    // `unfold` isn't `Unpin`, since the `async` block isn't
+    let s = stream::unfold(Some(body), |body| async move {
+        tokio::task::yield_now().await;
+        body.map(|body| (Ok(Bytes::from(body)), None))
+    });
+    let mut parts = FormData::new(s, boundary);
+
+    {
+        let mut part1 = parts.next().await.unwrap().unwrap();
+        assert_eq!(part1.raw_headers().parse().unwrap().name, "foo");
+        assert_eq!(part1.read_to_end().await.unwrap(), "bar");
+    }
+
+    {
+        assert!(parts.next().await.is_none());
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn nested() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"files\"\r\n\
+         content-type: multipart/mixed; boundary={1}\r\n\r\n\
+         --{1}\r\n\
+         content-disposition: attachment; filename=\"a.txt\"\r\n\r\n\
+         abc\r\n\
+         --{1}\r\n\
+         content-disposition: attachment; filename=\"b.txt\"\r\n\r\n\
+         def\r\n\
+         --{1}--\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary, "nested"
+    );
+
+    for &chunk_size in &[7, 100, body.len()] {
+        let chunks = body
+            .as_bytes()
+            .chunks(chunk_size)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>();
+        let s = stream::iter(chunks);
+        let mut parts = FormData::new(s, boundary);
+
+        {
+            let part1 = parts.next().await.unwrap().unwrap();
+            let mut nested = part1.into_nested().unwrap();
+
+            let mut file1 = nested.next().await.unwrap().unwrap();
+            let filename1 = file1.raw_headers().filename().unwrap();
+            assert_eq!(filename1.as_deref(), Some("a.txt"));
+            assert_eq!(file1.read_to_end().await.unwrap(), "abc");
+
+            let mut file2 = nested.next().await.unwrap().unwrap();
+            let filename2 = file2.raw_headers().filename().unwrap();
+            assert_eq!(filename2.as_deref(), Some("b.txt"));
+            assert_eq!(file2.read_to_end().await.unwrap(), "def");
+
+            assert!(nested.next().await.is_none());
+        }
+
+        {
+            let part2 = parts.next().await.unwrap().unwrap();
+            let mut part2 = part2.into_nested().unwrap_err();
+            assert_eq!(part2.read_to_end().await.unwrap(), "bar");
+        }
+
+        {
+            assert!(parts.next().await.is_none());
+        }
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn buffered_parts() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"abcd\"; filename=\"test.txt\"\r\n\r\n\
+         efgh\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"baz\"\r\n\r\n\
+         \r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    for &chunk_size in &[1, 7, body.len()] {
+        let chunks = body
+            .as_bytes()
+            .chunks(chunk_size)
+            .map(Bytes::copy_from_slice)
+            .collect::<Vec<_>>();
+        let s = stream::iter(chunks.clone().into_iter().map(Ok));
+        let parts = FormData::new(s, boundary)
+            .buffered_parts(4)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        let parts = parts
+            .into_iter()
+            .map(|(headers, body)| (headers.name, headers.filename, body))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parts,
+            vec![
+                ("foo".to_owned(), None, Bytes::from_static(b"bar")),
+                (
+                    "abcd".to_owned(),
+                    Some("test.txt".to_owned()),
+                    Bytes::from_static(b"efgh")
+                ),
+                ("baz".to_owned(), None, Bytes::new()),
+            ]
+        );
+
+        let s = stream::iter(chunks.into_iter().map(Ok));
+        let mut parts = FormData::new(s, boundary).buffered_parts(3);
+        let (headers, body) = parts.next().await.unwrap().unwrap();
+        assert_eq!(headers.name, "foo");
+        assert_eq!(body, "bar");
+
+        let err = parts.next().await.unwrap().unwrap_err();
+        assert_eq!(
+            Error::from_io_error(&err),
+            Some(&Error::PartTooLarge { limit: 3 })
+        );
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn builder() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\n\
+         content-disposition: form-data; name=\"foo\"\n\n\
+         bar\n\
+         --{0}\n\
+         content-disposition: form-data; name=\"abcd\"\n\n\
+         efgh\n\
+         --{0}--\n\
+         ",
+        boundary
+    );
+
+    let s = stream::iter(vec![Ok(Bytes::from(body.clone()))]);
+    let parts = FormData::builder()
+        .lenient(true)
+        .max_parts(2)
+        .capacity(64)
+        .build(s, boundary)
+        .buffered_parts(4)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let names = parts
+        .into_iter()
+        .map(|(headers, _body)| headers.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["foo", "abcd"]);
+
+    let s = stream::iter(vec![Ok(Bytes::from(body))]);
+    let mut form_data = FormData::builder().max_parts(2).build(s, boundary);
+    assert!(form_data.next().await.unwrap().is_err());
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn builder_options() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\
+         content-length: 2\r\n\r\n\
+         {1}bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary, '\u{feff}'
+    );
+
+    let s = stream::iter(vec![Ok(Bytes::from(body.clone()))]);
+    let mut form_data = FormData::builder().strip_bom(true).build(s, boundary);
+    let mut part = form_data.next().await.unwrap().unwrap();
+    assert_eq!(part.read_to_end().await.unwrap(), "bar");
+
+    let s = stream::iter(vec![Ok(Bytes::from(body.clone()))]);
+    let mut form_data = FormData::builder()
+        .collect_headers(false)
+        .build(s, boundary);
+    let part = form_data.next().await.unwrap().unwrap();
+    assert!(part.raw_headers().name().is_err());
+
+    let s = stream::iter(vec![Ok(Bytes::from(body))]);
+    let mut form_data = FormData::builder()
+        .validate_content_length(true)
+        .build(s, boundary);
+    let mut part = form_data.next().await.unwrap().unwrap();
+    assert!(part.read_to_end().await.is_err());
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn find_part() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"upload\"; filename=\"test.txt\"\r\n\r\n\
+         efgh\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"baz\"\r\n\r\n\
+         \r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    for &chunk_size in &[1, 7, body.len()] {
+        let chunks = body
+            .as_bytes()
+            .chunks(chunk_size)
+            .map(Bytes::copy_from_slice)
+            .collect::<Vec<_>>();
+
+        let s = stream::iter(chunks.clone().into_iter().map(Ok));
+        let mut part = FormData::new(s, boundary)
+            .find_part("upload")
+            .await
+            .unwrap()
+            .unwrap();
+        let filename = part.raw_headers().filename().unwrap();
+        assert_eq!(filename.as_deref(), Some("test.txt"));
+        assert_eq!(part.index(), 1);
+        assert_eq!(part.read_to_end().await.unwrap(), "efgh");
+
+        let s = stream::iter(chunks.into_iter().map(Ok));
+        let part = FormData::new(s, boundary)
+            .find_part("missing")
+            .await
+            .unwrap();
+        assert!(part.is_none());
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn part_headers() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"; filename=\"test.txt\"\r\n\
+         content-type: text/plain\r\n\r\n\
+         bar\r\n\
+         --{0}\r\n\
+         content-type: text/plain\r\n\r\n\
+         efgh\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    let s = stream::iter(vec![Ok(Bytes::from(body))]);
+    let mut form_data = FormData::new(s, boundary);
+
+    let part1 = form_data.next().await.unwrap().unwrap();
+    let headers = part1.headers().unwrap();
+    assert_eq!(headers.name, "foo");
+    assert_eq!(headers.filename.as_deref(), Some("test.txt"));
+    assert_eq!(headers.content_type.as_deref(), Some("text/plain"));
+    assert_eq!(
+        part1.headers().unwrap(),
+        &part1.raw_headers().parse().unwrap()
+    );
+
+    let part2 = form_data.next().await.unwrap().unwrap();
+    assert_eq!(
+        part2.headers().unwrap_err(),
+        part2.raw_headers().parse().unwrap_err()
+    );
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn part_size_hint() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\
+         content-length: 3\r\n\r\n\
+         bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    let s = stream::iter(vec![Ok(Bytes::from(body.clone()))]);
+    let mut form_data = FormData::new(s, boundary);
+    let part = form_data.next().await.unwrap().unwrap();
+    assert_eq!(part.size_hint(), (0, None));
+
+    let s = stream::iter(vec![Ok(Bytes::from(body))]);
+    let mut form_data = FormData::new(s, boundary);
+    form_data.set_validate_content_length(true);
+    let mut part = form_data.next().await.unwrap().unwrap();
+    assert_eq!(part.size_hint(), (0, Some(4)));
+    assert_eq!(part.next().await.unwrap().unwrap(), "bar");
+    assert_eq!(part.size_hint(), (0, Some(1)));
+    assert!(part.next().await.is_none());
+    assert_eq!(part.size_hint(), (0, Some(0)));
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn skip_part_without_pending() {
+    use futures_util::FutureExt;
+
+    let boundary = "--abcdef1234--";
+    let mut chunks = vec![Ok(Bytes::from(format!(
+        "--{}\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\n",
+        boundary
+    )))];
+    chunks.extend((0..1000).map(|_| Ok(Bytes::from_static(b"abcdefgh"))));
+    chunks.push(Ok(Bytes::from(format!(
+        "\r\n--{0}\r\ncontent-disposition: form-data; name=\"bar\"\r\n\r\nbaz\r\n--{0}--\r\n",
+        boundary
+    ))));
+
+    let mut form_data = FormData::new(stream::iter(chunks), boundary);
+    let part1 = form_data.next().await.unwrap().unwrap();
+    assert_eq!(part1.raw_headers().name().unwrap(), "foo");
+
+    // Skipping the body of `part1` never has to wait on the underlying stream
+    let part2 = form_data.next().now_or_never().unwrap().unwrap().unwrap();
+    assert_eq!(part2.raw_headers().name().unwrap(), "bar");
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn skip_part() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"; filename=\"foo.txt\"\r\n\r\n\
+         {1}\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"bar\"\r\n\r\n\
+         baz\r\n\
+         --{0}--\r\n\
+         ",
+        boundary,
+        "a".repeat(64 * 1024)
+    );
+
+    let s = stream::iter(
+        body.into_bytes()
+            .chunks(1000)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>(),
+    );
+    let mut form_data = FormData::new(s, boundary);
+
+    let part1 = form_data.next().await.unwrap().unwrap();
+    part1.skip().await.unwrap();
+
+    let mut part2 = form_data.next().await.unwrap().unwrap();
+    assert_eq!(part2.raw_headers().name().unwrap(), "bar");
+    assert_eq!(part2.read_to_end().await.unwrap(), "baz");
+    assert!(form_data.next().await.is_none());
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn plain_stream_error() {
+    use multiparty::server::plain_futures03::{self, FormDataError, Read};
+
+    #[derive(Debug, PartialEq)]
+    struct StreamError;
+
+    let boundary = "--abcdef1234--";
+    let head = format!(
+        "--{}\r\ncontent-disposition: form-data; name=\"foo\"\r\n\r\nbar",
+        boundary
+    );
+    let chunks = vec![Ok(Bytes::from(head)), Err(StreamError)];
+    let form_data = plain_futures03::FormData::new(stream::iter(chunks), boundary);
+    futures_util::pin_mut!(form_data);
+
+    assert!(matches!(
+        form_data.next().await,
+        Some(Ok(Read::NewPart { index: 0, .. }))
+    ));
+    assert!(matches!(
+        form_data.next().await,
+        Some(Err(FormDataError::Stream(StreamError)))
+    ));
+
+    let body = format!("--{0}\r\n\r\nbar\r\n--{0}x", boundary);
+    let chunks = vec![Ok::<_, StreamError>(Bytes::from(body))];
+    let form_data = plain_futures03::FormData::new(stream::iter(chunks), boundary);
+    futures_util::pin_mut!(form_data);
+    assert!(matches!(
+        form_data.next().await,
+        Some(Ok(Read::NewPart { index: 0, .. }))
+    ));
+    assert!(matches!(form_data.next().await, Some(Ok(Read::Part(bytes))) if bytes == "bar"));
+    assert!(matches!(form_data.next().await, Some(Ok(Read::PartEof))));
+    assert!(matches!(
+        form_data.next().await,
+        Some(Err(FormDataError::Decode(Error::UnexpectedEof)))
+    ));
+}
+
+#[cfg(all(feature = "server", feature = "futures03"))]
+#[tokio::test]
+async fn drop_mid_poll() {
+    use futures_util::future::FutureExt;
+
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         {1}\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"baz\"\r\n\r\n\
+         qux\r\n\
+         --{0}--\r\n\
+         ",
+        boundary,
+        "bar".repeat(10)
+    );
+    let chunks = body
+        .as_bytes()
+        .chunks(7)
+        .map(Bytes::copy_from_slice)
+        .collect::<Vec<_>>();
+
+    // Every chunk makes the stream return `Poll::Pending` once,
+    // like a slow client would
+    let slow_stream = |chunks: Vec<Bytes>| {
+        Box::pin(stream::iter(chunks).then(|chunk| async move {
+            tokio::task::yield_now().await;
+            Ok(chunk)
+        }))
+    };
+
+    let mut form_data = FormData::new(slow_stream(chunks.clone()), boundary);
+    assert!(form_data.next().now_or_never().is_none());
+
+    let mut part = form_data.next().await.unwrap().unwrap();
+    assert_eq!(part.raw_headers().name().unwrap(), "foo");
+    // Dropping the future, like a timeout would, cancels the read
+    // without breaking the `Part` or the `FormData`, and retrying it
+    // resumes from where it was cancelled
+    let mut cancelled = 0;
+    let read = loop {
+        match part.read_to_end().now_or_never() {
+            Some(read) => break read.unwrap(),
+            None => cancelled += 1,
+        }
+    };
+    assert!(cancelled > 1);
+    assert_eq!(read, "bar".repeat(10));
+    drop(part);
+
+    let mut part = form_data.next().await.unwrap().unwrap();
+    assert_eq!(part.raw_headers().name().unwrap(), "baz");
+    assert_eq!(part.read_to_end().await.unwrap(), "qux");
+    assert!(form_data.next().await.is_none());
+
+    // What a cancelled `read_to_end` read is yielded first by the `Stream`
+    let mut form_data = FormData::new(slow_stream(chunks.clone()), boundary);
+    let mut part = form_data.next().await.unwrap().unwrap();
+    for _ in 0..3 {
+        assert!(part.read_to_end().now_or_never().is_none());
+    }
+    let mut read = Vec::new();
+    while let Some(bytes) = part.try_next().await.unwrap() {
+        read.extend_from_slice(&bytes);
+    }
+    assert_eq!(read, "bar".repeat(10).as_bytes());
+    drop(part);
+
+    // Drop the whole `FormData` in the middle of a part
+    let mut form_data = FormData::new(slow_stream(chunks.clone()), boundary);
+    let mut part = form_data.next().await.unwrap().unwrap();
+    assert!(part.read_to_end().now_or_never().is_none());
+    drop(part);
+    drop(form_data);
+
+    // Decoding starts over from a fresh stream
+    let mut form_data = FormData::new(slow_stream(chunks), boundary);
+    let mut names = Vec::new();
+    while let Some(mut part) = form_data.try_next().await.unwrap() {
+        names.push(part.raw_headers().name().unwrap().into_owned());
+        part.read_to_end().await.unwrap();
+    }
+    assert_eq!(names, ["foo", "baz"]);
+}
@@ -0,0 +1,167 @@
+#[cfg(all(feature = "server", feature = "futures-io"))]
+use std::io;
+#[cfg(all(feature = "server", feature = "futures-io"))]
+use std::pin::Pin;
+#[cfg(all(feature = "server", feature = "futures-io"))]
+use std::task::{Context, Poll};
+
+#[cfg(all(feature = "server", feature = "futures-io"))]
+use futures_io::AsyncRead;
+#[cfg(all(feature = "server", feature = "futures-io"))]
+use futures_util::io::AsyncReadExt;
+#[cfg(all(feature = "server", feature = "futures-io"))]
+use multiparty::server::futures_io::FormData;
+#[cfg(all(feature = "server", feature = "futures-io"))]
+use multiparty::server::sans_io;
+
+/// A reader yielding at most `chunk_size` bytes at a time, sometimes returning `Pending` first
+#[cfg(all(feature = "server", feature = "futures-io"))]
+struct ChunkedReader<'a> {
+    bytes: &'a [u8],
+    chunk_size: usize,
+}
+
+#[cfg(all(feature = "server", feature = "futures-io"))]
+impl<'a> AsyncRead for ChunkedReader<'a> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if fastrand::bool() {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        let len = buf.len().min(self.chunk_size).min(self.bytes.len());
+        buf[..len].copy_from_slice(&self.bytes[..len]);
+        self.bytes = &self.bytes[len..];
+        Poll::Ready(Ok(len))
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures-io"))]
+#[tokio::test]
+async fn empty() {
+    let boundary = "--abcdef1234--";
+
+    let mut parts = FormData::new(futures_util::io::empty(), boundary);
+    assert!(parts.next_part().await.unwrap().is_none());
+    assert!(parts.next_part().await.unwrap().is_none());
+}
+
+#[cfg(all(feature = "server", feature = "futures-io"))]
+#[tokio::test]
+async fn multipart() {
+    let body_contents = (0..4096)
+        .map(|_| fastrand::alphanumeric())
+        .collect::<String>();
+
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"abcd\"; filename=\"test.txt\"\r\n\r\n\
+         {1}\r\n\
+         --{0}--\r\n\
+         ",
+        boundary, body_contents
+    );
+
+    for &chunk_size in &[1, 7, 32, body.len()] {
+        let reader = ChunkedReader {
+            bytes: body.as_bytes(),
+            chunk_size,
+        };
+        let mut parts = FormData::new(reader, boundary);
+
+        {
+            let mut part1 = parts.next_part().await.unwrap().unwrap();
+            let headers1 = part1.raw_headers().parse().unwrap();
+            assert_eq!(headers1.name, "foo");
+            assert!(headers1.filename.is_none());
+
+            let mut bytes1 = String::new();
+            part1.read_to_string(&mut bytes1).await.unwrap();
+            assert_eq!(bytes1, "bar");
+        }
+
+        {
+            let mut part2 = parts.next_part().await.unwrap().unwrap();
+            let headers2 = part2.raw_headers().parse().unwrap();
+            assert_eq!(headers2.name, "abcd");
+            assert_eq!(headers2.filename.as_deref(), Some("test.txt"));
+
+            let mut bytes2 = String::new();
+            part2.read_to_string(&mut bytes2).await.unwrap();
+            assert_eq!(bytes2, body_contents);
+        }
+
+        assert!(parts.next_part().await.unwrap().is_none());
+    }
+}
+
+#[cfg(all(feature = "server", feature = "futures-io"))]
+#[tokio::test]
+async fn multipart_skip1() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"abcd\"\r\n\r\n\
+         efgh\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    let mut parts = FormData::new(body.as_bytes(), boundary);
+
+    {
+        let part1 = parts.next_part().await.unwrap().unwrap();
+        let headers1 = part1.raw_headers().parse().unwrap();
+        assert_eq!(headers1.name, "foo");
+    }
+
+    {
+        let mut part2 = parts.next_part().await.unwrap().unwrap();
+        let headers2 = part2.raw_headers().parse().unwrap();
+        assert_eq!(headers2.name, "abcd");
+
+        let mut bytes2 = String::new();
+        part2.read_to_string(&mut bytes2).await.unwrap();
+        assert_eq!(bytes2, "efgh");
+    }
+
+    assert!(parts.next_part().await.unwrap().is_none());
+}
+
+#[cfg(all(feature = "server", feature = "futures-io"))]
+#[tokio::test]
+async fn from_sans_io() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    let inner = sans_io::FormData::builder()
+        .max_part_size(2)
+        .build(boundary);
+    let mut parts = FormData::from_sans_io(body.as_bytes(), inner);
+
+    let mut part1 = parts.next_part().await.unwrap().unwrap();
+    let mut body = Vec::new();
+    assert!(part1.read_to_end(&mut body).await.is_err());
+}
@@ -0,0 +1,65 @@
+#[cfg(all(feature = "server", feature = "hyper"))]
+use std::collections::VecDeque;
+#[cfg(all(feature = "server", feature = "hyper"))]
+use std::convert::Infallible;
+#[cfg(all(feature = "server", feature = "hyper"))]
+use std::pin::Pin;
+#[cfg(all(feature = "server", feature = "hyper"))]
+use std::task::{Context, Poll};
+
+#[cfg(all(feature = "server", feature = "hyper"))]
+use bytes::Bytes;
+#[cfg(all(feature = "server", feature = "hyper"))]
+use futures_util::stream::TryStreamExt;
+#[cfg(all(feature = "server", feature = "hyper"))]
+use http::HeaderMap;
+#[cfg(all(feature = "server", feature = "hyper"))]
+use http_body::{Body, Frame};
+
+#[cfg(all(feature = "server", feature = "hyper"))]
+use multiparty::server::hyper::from_body;
+
+#[cfg(all(feature = "server", feature = "hyper"))]
+struct Frames(VecDeque<Frame<Bytes>>);
+
+#[cfg(all(feature = "server", feature = "hyper"))]
+impl Body for Frames {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Poll::Ready(self.0.pop_front().map(Ok))
+    }
+}
+
+#[cfg(all(feature = "server", feature = "hyper"))]
+#[tokio::test]
+async fn body() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+    let (head, tail) = body.split_at(body.len() / 2);
+
+    let frames = vec![
+        Frame::data(Bytes::copy_from_slice(head.as_bytes())),
+        Frame::trailers(HeaderMap::new()),
+        Frame::data(Bytes::copy_from_slice(tail.as_bytes())),
+    ];
+    let mut multipart = from_body(Frames(frames.into()), boundary);
+
+    let mut part1 = multipart.try_next().await.unwrap().unwrap();
+    assert_eq!(part1.raw_headers().parse().unwrap().name, "foo");
+    assert_eq!(part1.read_to_end().await.unwrap(), "bar");
+
+    assert!(multipart.try_next().await.unwrap().is_none());
+}
@@ -0,0 +1,60 @@
+#[cfg(all(feature = "server", feature = "tokio-util"))]
+use futures_util::stream::TryStreamExt;
+#[cfg(all(feature = "server", feature = "tokio-util"))]
+use tokio_util::codec::FramedRead;
+
+#[cfg(all(feature = "server", feature = "tokio-util"))]
+use multiparty::server::tokio_util::{Frame, MultipartCodec};
+
+#[cfg(all(feature = "server", feature = "tokio-util"))]
+#[tokio::test]
+async fn framed() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"abcd\"\r\n\r\n\
+         efgh\r\n\
+         --{0}--\r\n\
+         epilogue",
+        boundary
+    );
+
+    let mut frames = FramedRead::new(body.as_bytes(), MultipartCodec::new(boundary));
+
+    let mut parts = Vec::new();
+    let mut eof = false;
+    while let Some(frame) = frames.try_next().await.unwrap() {
+        assert!(!eof);
+        match frame {
+            Frame::NewPart { headers } => {
+                parts.push((headers.parse().unwrap().name, Vec::new()));
+            }
+            Frame::Part(bytes) => parts.last_mut().unwrap().1.extend_from_slice(&bytes),
+            Frame::PartEof => {}
+            Frame::Eof => eof = true,
+        }
+    }
+
+    assert!(eof);
+    assert_eq!(
+        parts,
+        vec![
+            ("foo".to_owned(), b"bar".to_vec()),
+            ("abcd".to_owned(), b"efgh".to_vec())
+        ]
+    );
+}
+
+#[cfg(all(feature = "server", feature = "tokio-util"))]
+#[tokio::test]
+async fn framed_bad_suffix() {
+    let boundary = "--abcdef1234--";
+    let body = format!("--{0}??\r\n", boundary);
+
+    let mut frames = FramedRead::new(body.as_bytes(), MultipartCodec::new(boundary));
+    assert!(frames.try_next().await.is_err());
+}
@@ -0,0 +1,98 @@
+#[cfg(all(feature = "server", feature = "warp"))]
+use futures_util::stream::TryStreamExt;
+
+#[cfg(all(feature = "server", feature = "warp"))]
+use multiparty::server::owned_futures03::FormDataBuilder;
+#[cfg(all(feature = "server", feature = "warp"))]
+use multiparty::server::warp::{form_data, form_data_with, MultipartRejection};
+
+#[cfg(all(feature = "server", feature = "warp"))]
+#[tokio::test]
+async fn extract() {
+    let boundary = "--abcdef1234--";
+    let body = format!(
+        "\
+         --{0}\r\n\
+         content-disposition: form-data; name=\"foo\"\r\n\r\n\
+         bar\r\n\
+         --{0}--\r\n\
+         ",
+        boundary
+    );
+
+    let mut multipart = warp::test::request()
+        .header(
+            "content-type",
+            format!("multipart/form-data; boundary=\"{}\"", boundary),
+        )
+        .body(body)
+        .filter(&form_data())
+        .await
+        .unwrap();
+
+    let mut part1 = multipart.try_next().await.unwrap().unwrap();
+    assert_eq!(part1.raw_headers().parse().unwrap().name, "foo");
+    assert_eq!(part1.read_to_end().await.unwrap(), "bar");
+
+    assert!(multipart.try_next().await.unwrap().is_none());
+}
+
+#[cfg(all(feature = "server", feature = "warp"))]
+#[tokio::test]
+async fn reject() {
+    let rejection = warp::test::request()
+        .filter(&form_data())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        rejection.find::<MultipartRejection>(),
+        Some(MultipartRejection::MissingContentType)
+    ));
+
+    let rejection = warp::test::request()
+        .header("content-type", "application/json")
+        .filter(&form_data())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        rejection.find::<MultipartRejection>(),
+        Some(MultipartRejection::InvalidContentType)
+    ));
+}
+
+#[cfg(all(feature = "server", feature = "warp"))]
+#[tokio::test]
+async fn limits() {
+    let boundary = "--abcdef1234--";
+    let body = |contents: &str| {
+        format!(
+            "\
+             --{0}\r\n\
+             content-disposition: form-data; name=\"foo\"\r\n\r\n\
+             {1}\r\n\
+             --{0}--\r\n\
+             ",
+            boundary, contents
+        )
+    };
+    let content_type = format!("multipart/form-data; boundary=\"{}\"", boundary);
+
+    // Over the 2 MB applied by default
+    let mut multipart = warp::test::request()
+        .header("content-type", &content_type)
+        .body(body(&"a".repeat(3 * 1024 * 1024)))
+        .filter(&form_data())
+        .await
+        .unwrap();
+    let mut part1 = multipart.try_next().await.unwrap().unwrap();
+    assert!(part1.read_to_end().await.is_err());
+
+    let mut multipart = warp::test::request()
+        .header("content-type", &content_type)
+        .body(body("bar"))
+        .filter(&form_data_with(FormDataBuilder::new().max_part_size(2)))
+        .await
+        .unwrap();
+    let mut part1 = multipart.try_next().await.unwrap().unwrap();
+    assert!(part1.read_to_end().await.is_err());
+}